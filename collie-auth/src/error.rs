@@ -3,6 +3,30 @@ pub enum Error {
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("TOTP code required")]
+    TotpRequired,
+
+    #[error("too many attempts, retry after {0} seconds")]
+    TooManyAttempts(i64),
+
+    #[error("forbidden: `{0}` scope is required")]
+    Forbidden(String),
+
+    #[error("bad argument")]
+    BadArgument,
+
+    #[error("failed to hash password")]
+    PasswordHashingFailure,
+
+    #[error("invalid key `{0}` for `{1}`")]
+    InvalidEnumKey(String, String),
+
+    #[error(transparent)]
+    CoreError {
+        #[from]
+        source: collie_core::error::Error,
+    },
+
     #[error(transparent)]
     RusqliteError {
         #[from]
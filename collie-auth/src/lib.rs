@@ -1,16 +1,50 @@
 pub mod model {
+    pub mod attempt;
+    pub mod audit;
+    pub mod credential;
     pub mod key;
+    pub mod revocation;
+    pub mod scope;
+    pub mod signature;
     pub mod token;
+    pub mod totp;
 }
 
 pub mod repository {
+    pub mod attempt;
+    pub mod audit;
+    pub mod credential;
     pub mod database;
     pub mod key;
+    pub mod nonce;
+    pub mod revocation;
+    pub mod totp;
 }
 
 pub mod service {
+    pub mod attempt;
+    pub mod audit;
     pub mod key;
+    pub mod password;
+    pub mod signature;
     pub mod token;
+    pub mod totp;
+}
+
+pub mod util {
+    pub mod clock;
 }
 
 pub mod error;
+
+#[cfg(test)]
+mod tests {
+    mod common;
+
+    mod attempt;
+    mod audit;
+    mod password;
+    mod signature;
+    mod token;
+    mod totp;
+}
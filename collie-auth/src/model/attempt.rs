@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+
+pub struct AttemptToCreate {
+    pub access: String,
+    pub source: Option<String>,
+    pub attempted_at: DateTime<Utc>,
+}
+
+/// Number of failed attempts in a window and when the last one happened.
+pub struct Failures {
+    pub count: u32,
+    pub last_attempted_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,132 @@
+use chrono::{DateTime, FixedOffset};
+use core::fmt;
+use rusqlite::Row;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crate::error::Error;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AuditEvent {
+    TokenIssue,
+    TokenRevoke,
+    KeyCreate,
+    KeyRevoke,
+    KeyRotate,
+    UserRegister,
+    PasswordChange,
+}
+
+impl Display for AuditEvent {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::TokenIssue => write!(f, "token_issue"),
+            Self::TokenRevoke => write!(f, "token_revoke"),
+            Self::KeyCreate => write!(f, "key_create"),
+            Self::KeyRevoke => write!(f, "key_revoke"),
+            Self::KeyRotate => write!(f, "key_rotate"),
+            Self::UserRegister => write!(f, "user_register"),
+            Self::PasswordChange => write!(f, "password_change"),
+        }
+    }
+}
+
+impl FromStr for AuditEvent {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "token_issue" => Ok(Self::TokenIssue),
+            "token_revoke" => Ok(Self::TokenRevoke),
+            "key_create" => Ok(Self::KeyCreate),
+            "key_revoke" => Ok(Self::KeyRevoke),
+            "key_rotate" => Ok(Self::KeyRotate),
+            "user_register" => Ok(Self::UserRegister),
+            "password_change" => Ok(Self::PasswordChange),
+            _ => Err(Error::InvalidEnumKey(
+                x.to_string(),
+                "AuditEvent".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+    Locked,
+}
+
+impl Display for AuditOutcome {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Success => write!(f, "success"),
+            Self::Failure => write!(f, "failure"),
+            Self::Locked => write!(f, "locked"),
+        }
+    }
+}
+
+impl FromStr for AuditOutcome {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "success" => Ok(Self::Success),
+            "failure" => Ok(Self::Failure),
+            "locked" => Ok(Self::Locked),
+            _ => Err(Error::InvalidEnumKey(
+                x.to_string(),
+                "AuditOutcome".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Audit {
+    pub id: i32,
+    pub event: AuditEvent,
+    pub key: Option<i32>,
+    pub user: Option<i32>,
+    pub client: Option<String>,
+    pub outcome: AuditOutcome,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl From<&Row<'_>> for Audit {
+    fn from(row: &Row) -> Self {
+        Self {
+            id: row.get_unwrap("id"),
+            event: AuditEvent::from_str(&row.get_unwrap::<&str, String>("event")).unwrap(),
+            key: row.get_unwrap("key"),
+            user: row.get_unwrap("user"),
+            client: row.get_unwrap("client"),
+            outcome: AuditOutcome::from_str(&row.get_unwrap::<&str, String>("outcome")).unwrap(),
+            created_at: row.get_unwrap("created_at"),
+        }
+    }
+}
+
+pub struct AuditToCreate {
+    pub event: AuditEvent,
+    pub key: Option<i32>,
+    pub user: Option<i32>,
+    pub client: Option<String>,
+    pub outcome: AuditOutcome,
+}
+
+#[derive(Deserialize, Default)]
+pub struct AuditReadOption {
+    pub events: Option<Vec<AuditEvent>>,
+    pub key: Option<i32>,
+    pub user: Option<i32>,
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
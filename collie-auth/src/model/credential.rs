@@ -0,0 +1,32 @@
+use chrono::{DateTime, FixedOffset};
+use rusqlite::Row;
+use serde::Deserialize;
+
+pub struct Credential {
+    pub id: i32,
+    pub user: i32,
+    pub hash: String,
+    pub updated_at: DateTime<FixedOffset>,
+}
+
+impl From<&Row<'_>> for Credential {
+    fn from(row: &Row) -> Self {
+        Self {
+            id: row.get_unwrap("id"),
+            user: row.get_unwrap("user"),
+            hash: row.get_unwrap("hash"),
+            updated_at: row.get_unwrap("updated_at"),
+        }
+    }
+}
+
+pub struct CredentialToCreate {
+    pub user: i32,
+    pub hash: String,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct PasswordLogin {
+    pub name: String,
+    pub password: String,
+}
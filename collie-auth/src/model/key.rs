@@ -2,12 +2,16 @@ use chrono::{DateTime, FixedOffset};
 use rusqlite::Row;
 use serde::{Deserialize, Serialize};
 
+use super::scope::Scope;
+
 #[derive(Serialize)]
 pub struct Key {
     pub id: i32,
+    pub user: Option<i32>,
     pub access: String,
     pub secret: String,
     pub description: Option<String>,
+    pub scopes: Vec<Scope>,
     pub expired_at: Option<DateTime<FixedOffset>>,
 }
 
@@ -15,9 +19,19 @@ impl From<&Row<'_>> for Key {
     fn from(row: &Row) -> Self {
         Self {
             id: row.get_unwrap("id"),
+            user: row.get_unwrap("user"),
             access: row.get_unwrap("access"),
             secret: row.get_unwrap("secret"),
             description: row.get_unwrap("description"),
+            // Scopes this version doesn't know, e.g. ones written by a newer one, grant nothing.
+            scopes: row.get_unwrap::<&str, Option<String>>("scopes").map_or(
+                Scope::ALL.to_vec(),
+                |x| {
+                    x.split_whitespace()
+                        .filter_map(|x| x.parse().ok())
+                        .collect()
+                },
+            ),
             expired_at: row.get_unwrap("expired_at"),
         }
     }
@@ -25,8 +39,10 @@ impl From<&Row<'_>> for Key {
 
 #[derive(Deserialize)]
 pub struct KeyToCreate {
+    pub user: Option<i32>,
     pub access: String,
     pub secret: String,
     pub description: Option<String>,
+    pub scopes: Option<Vec<Scope>>,
     pub expired_at: Option<DateTime<FixedOffset>>,
 }
@@ -0,0 +1,8 @@
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct RevocationToCreate {
+    pub jti: String,
+    pub expired_at: DateTime<FixedOffset>,
+}
@@ -0,0 +1,64 @@
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crate::error::Error;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Scope {
+    #[serde(rename = "feeds:read")]
+    FeedsRead,
+    #[serde(rename = "feeds:write")]
+    FeedsWrite,
+    #[serde(rename = "items:read")]
+    ItemsRead,
+    #[serde(rename = "items:write")]
+    ItemsWrite,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 4] = [
+        Self::FeedsRead,
+        Self::FeedsWrite,
+        Self::ItemsRead,
+        Self::ItemsWrite,
+    ];
+
+    pub const READ_ONLY: [Scope; 2] = [Self::FeedsRead, Self::ItemsRead];
+
+    pub fn join(scopes: &[Scope]) -> String {
+        scopes
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::FeedsRead => write!(f, "feeds:read"),
+            Self::FeedsWrite => write!(f, "feeds:write"),
+            Self::ItemsRead => write!(f, "items:read"),
+            Self::ItemsWrite => write!(f, "items:write"),
+        }
+    }
+}
+
+impl FromStr for Scope {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "feeds:read" => Ok(Self::FeedsRead),
+            "feeds:write" => Ok(Self::FeedsWrite),
+            "items:read" => Ok(Self::ItemsRead),
+            "items:write" => Ok(Self::ItemsWrite),
+            _ => Err(Error::InvalidEnumKey(x.to_string(), "Scope".to_string())),
+        }
+    }
+}
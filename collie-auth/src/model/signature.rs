@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+
+/// A request signed by a client with the secret of its key.
+pub struct SignedRequest<'a> {
+    pub access: &'a str,
+    pub method: &'a str,
+    pub path: &'a str,
+    /// Unix time at which the client signed the request.
+    pub timestamp: i64,
+    pub nonce: &'a str,
+    pub body: &'a [u8],
+    /// Hex-encoded HMAC-SHA256 of the canonical request.
+    pub signature: &'a str,
+}
+
+pub struct NonceToCreate {
+    pub access: String,
+    pub nonce: String,
+    pub expired_at: DateTime<Utc>,
+}
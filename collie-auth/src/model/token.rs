@@ -1,9 +1,19 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+use super::scope::Scope;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Claims {
+    pub jti: String,
+    pub kid: Option<i32>,
+    pub user: Option<i32>,
+    pub scopes: Vec<Scope>,
     pub iat: i64,
     pub exp: i64,
+    /// When the password of `user` was set, for tokens issued with it,
+    /// so that they stop being valid once the password changes.
+    #[serde(default)]
+    pub password_at: Option<i64>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -0,0 +1,35 @@
+use chrono::{DateTime, FixedOffset};
+use rusqlite::Row;
+use serde::Serialize;
+
+pub struct Totp {
+    pub id: i32,
+    pub user: i32,
+    pub secret: String,
+    pub last_step: Option<i64>,
+    pub enabled_at: Option<DateTime<FixedOffset>>,
+}
+
+impl From<&Row<'_>> for Totp {
+    fn from(row: &Row) -> Self {
+        Self {
+            id: row.get_unwrap("id"),
+            user: row.get_unwrap("user"),
+            secret: row.get_unwrap("secret"),
+            last_step: row.get_unwrap("last_step"),
+            enabled_at: row.get_unwrap("enabled_at"),
+        }
+    }
+}
+
+pub struct TotpToCreate {
+    pub user: i32,
+    pub secret: String,
+}
+
+/// What a user needs to add the account to an authenticator app.
+#[derive(Serialize)]
+pub struct Enrollment {
+    pub secret: String,
+    pub uri: String,
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use collie_core::repository::database::DbConnection;
+use sea_query::{Expr, Func, Query, SimpleExpr, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{
+    error::Result,
+    model::attempt::{AttemptToCreate, Failures},
+};
+
+use super::database::Attempts;
+
+pub fn create(conn: &DbConnection, arg: &AttemptToCreate) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Attempts::Table)
+        .columns([Attempts::Access, Attempts::Source, Attempts::AttemptedAt])
+        .values_panic([
+            (*arg.access).into(),
+            arg.source.clone().into(),
+            arg.attempted_at.into(),
+        ])
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn count_by_access(
+    conn: &DbConnection,
+    access: &str,
+    since: DateTime<Utc>,
+) -> Result<Failures> {
+    count(conn, Expr::col(Attempts::Access).eq(access), since)
+}
+
+pub fn count_by_source(
+    conn: &DbConnection,
+    source: &str,
+    since: DateTime<Utc>,
+) -> Result<Failures> {
+    count(conn, Expr::col(Attempts::Source).eq(source), since)
+}
+
+pub fn delete_by_access(conn: &DbConnection, access: &str) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Attempts::Table)
+        .and_where(Expr::col(Attempts::Access).eq(access))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn delete_before(conn: &DbConnection, before: DateTime<Utc>) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Attempts::Table)
+        .and_where(Expr::col(Attempts::AttemptedAt).lt(before))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+fn count(conn: &DbConnection, cond: SimpleExpr, since: DateTime<Utc>) -> Result<Failures> {
+    let (sql, values) = Query::select()
+        .expr(Func::count(Expr::col(Attempts::Id)))
+        .expr(Func::max(Expr::col(Attempts::AttemptedAt)))
+        .from(Attempts::Table)
+        .and_where(cond)
+        .and_where(Expr::col(Attempts::AttemptedAt).gt(since))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(if let Some(row) = rows.next()? {
+        Failures {
+            count: row.get_unwrap(0),
+            last_attempted_at: row.get_unwrap(1),
+        }
+    } else {
+        Failures {
+            count: 0,
+            last_attempted_at: None,
+        }
+    })
+}
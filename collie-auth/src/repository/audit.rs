@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use collie_core::repository::database::DbConnection;
+use sea_query::{Expr, Order, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{
+    error::Result,
+    model::audit::{Audit, AuditReadOption, AuditToCreate},
+};
+
+use super::database::Audits;
+
+pub fn create(conn: &DbConnection, arg: &AuditToCreate) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Audits::Table)
+        .columns([
+            Audits::Event,
+            Audits::Key,
+            Audits::User,
+            Audits::Client,
+            Audits::Outcome,
+            Audits::CreatedAt,
+        ])
+        .values_panic([
+            arg.event.to_string().into(),
+            arg.key.into(),
+            arg.user.into(),
+            arg.client.clone().into(),
+            arg.outcome.to_string().into(),
+            Utc::now().into(),
+        ])
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read_all(conn: &DbConnection, opt: &AuditReadOption) -> Result<Vec<Audit>> {
+    let mut query = Query::select()
+        .columns([
+            Audits::Id,
+            Audits::Event,
+            Audits::Key,
+            Audits::User,
+            Audits::Client,
+            Audits::Outcome,
+            Audits::CreatedAt,
+        ])
+        .from(Audits::Table)
+        .order_by(Audits::CreatedAt, Order::Desc)
+        .order_by(Audits::Id, Order::Desc)
+        .clone();
+
+    if let Some(events) = &opt.events {
+        query.and_where(Expr::col(Audits::Event).is_in(events.iter().map(ToString::to_string)));
+    }
+
+    if let Some(key) = opt.key {
+        query.and_where(Expr::col(Audits::Key).eq(key));
+    }
+
+    if let Some(user) = opt.user {
+        query.and_where(Expr::col(Audits::User).eq(user));
+    }
+
+    if let Some(since) = opt.since {
+        query.and_where(Expr::col(Audits::CreatedAt).gte(since.to_utc()));
+    }
+
+    if let Some(until) = opt.until {
+        query.and_where(Expr::col(Audits::CreatedAt).lt(until.to_utc()));
+    }
+
+    if let Some(limit) = opt.limit {
+        query.limit(limit);
+    }
+
+    if let Some(offset) = opt.offset {
+        query.offset(offset);
+    }
+
+    let db = conn.lock().unwrap();
+    let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Audit::from(x)))?;
+
+    Ok(rows
+        .map(std::result::Result::unwrap)
+        .collect::<Vec<Audit>>())
+}
+
+pub fn delete_before(conn: &DbConnection, before: DateTime<Utc>) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Audits::Table)
+        .and_where(Expr::col(Audits::CreatedAt).lt(before))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
@@ -0,0 +1,72 @@
+use chrono::Utc;
+use collie_core::repository::database::{DbConnection, Users};
+use sea_query::{Expr, OnConflict, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{
+    error::Result,
+    model::credential::{Credential, CredentialToCreate},
+};
+
+use super::database::Credentials;
+
+/// Creates the credential of a user, or replaces the existing one.
+pub fn upsert(conn: &DbConnection, arg: &CredentialToCreate) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Credentials::Table)
+        .columns([Credentials::User, Credentials::Hash, Credentials::UpdatedAt])
+        .values_panic([arg.user.into(), (*arg.hash).into(), Utc::now().into()])
+        .on_conflict(
+            OnConflict::column(Credentials::User)
+                .update_columns([Credentials::Hash, Credentials::UpdatedAt])
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read(conn: &DbConnection, user: i32) -> Result<Option<Credential>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Credentials::Id,
+            Credentials::User,
+            Credentials::Hash,
+            Credentials::UpdatedAt,
+        ])
+        .from(Credentials::Table)
+        .and_where(Expr::col(Credentials::User).eq(user))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Credential::from))
+}
+
+pub fn read_by_name(conn: &DbConnection, name: &str) -> Result<Option<Credential>> {
+    let (sql, values) = Query::select()
+        .columns([
+            (Credentials::Table, Credentials::Id),
+            (Credentials::Table, Credentials::User),
+            (Credentials::Table, Credentials::Hash),
+            (Credentials::Table, Credentials::UpdatedAt),
+        ])
+        .from(Credentials::Table)
+        .inner_join(
+            Users::Table,
+            Expr::col((Credentials::Table, Credentials::User)).equals((Users::Table, Users::Id)),
+        )
+        .and_where(Expr::col((Users::Table, Users::Name)).eq(name))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Credential::from))
+}
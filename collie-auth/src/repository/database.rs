@@ -1,12 +1,17 @@
-use sea_query::{ColumnDef, Iden, Table, TableStatement};
+use collie_core::repository::database::Users;
+use sea_query::{
+    ColumnDef, Expr, ForeignKey, ForeignKeyAction, Iden, Index, Table, TableStatement,
+};
 
 #[derive(Iden)]
 pub enum Keys {
     Table,
     Id,
+    User,
     Access,
     Secret,
     Description,
+    Scopes,
     ExpiredAt,
 }
 
@@ -21,10 +26,314 @@ pub fn keys_table() -> Vec<TableStatement> {
                 .auto_increment()
                 .primary_key(),
         )
+        .col(ColumnDef::new(Keys::User).integer())
         .col(ColumnDef::new(Keys::Access).text().not_null().unique_key())
         .col(ColumnDef::new(Keys::Secret).text().not_null())
         .col(ColumnDef::new(Keys::Description).text())
+        .col(ColumnDef::new(Keys::Scopes).text())
         .col(ColumnDef::new(Keys::ExpiredAt).date_time())
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_keys_users")
+                .from(Keys::Table, Keys::User)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    let alter = Table::alter()
+        .table(Keys::Table)
+        .add_column_if_not_exists(ColumnDef::new(Keys::Scopes).text())
+        .to_owned();
+
+    let add_user = Table::alter()
+        .table(Keys::Table)
+        .add_column_if_not_exists(ColumnDef::new(Keys::User).integer())
+        .to_owned();
+
+    vec![
+        TableStatement::Create(create),
+        TableStatement::Alter(alter),
+        TableStatement::Alter(add_user),
+    ]
+}
+
+#[derive(Iden)]
+pub enum Revocations {
+    Table,
+    Id,
+    Jti,
+    RevokedAt,
+    ExpiredAt,
+}
+
+pub fn revocations_table() -> Vec<TableStatement> {
+    let create = Table::create()
+        .table(Revocations::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Revocations::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(Revocations::Jti)
+                .text()
+                .not_null()
+                .unique_key(),
+        )
+        .col(
+            ColumnDef::new(Revocations::RevokedAt)
+                .date_time()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(Revocations::ExpiredAt)
+                .date_time()
+                .not_null(),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create)]
+}
+
+#[derive(Iden)]
+pub enum Attempts {
+    Table,
+    Id,
+    Access,
+    Source,
+    AttemptedAt,
+}
+
+pub fn attempts_table() -> Vec<TableStatement> {
+    let create = Table::create()
+        .table(Attempts::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Attempts::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Attempts::Access).text().not_null())
+        .col(ColumnDef::new(Attempts::Source).text())
+        .col(ColumnDef::new(Attempts::AttemptedAt).date_time().not_null())
+        .to_owned();
+
+    vec![TableStatement::Create(create)]
+}
+
+#[derive(Iden)]
+pub enum Audits {
+    Table,
+    Id,
+    Event,
+    Key,
+    User,
+    Client,
+    Outcome,
+    CreatedAt,
+}
+
+pub fn audits_table() -> Vec<TableStatement> {
+    let create = Table::create()
+        .table(Audits::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Audits::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(Audits::Event)
+                .text()
+                .check(Expr::col(Audits::Event).is_in([
+                    "token_issue",
+                    "token_revoke",
+                    "key_create",
+                    "key_revoke",
+                    "key_rotate",
+                    "user_register",
+                    "password_change",
+                ]))
+                .not_null(),
+        )
+        .col(ColumnDef::new(Audits::Key).integer())
+        .col(ColumnDef::new(Audits::User).integer())
+        .col(ColumnDef::new(Audits::Client).text())
+        .col(
+            ColumnDef::new(Audits::Outcome)
+                .text()
+                .check(Expr::col(Audits::Outcome).is_in(["success", "failure", "locked"]))
+                .not_null(),
+        )
+        .col(ColumnDef::new(Audits::CreatedAt).date_time().not_null())
+        .to_owned();
+
+    vec![TableStatement::Create(create)]
+}
+
+#[derive(Iden)]
+pub enum Credentials {
+    Table,
+    Id,
+    User,
+    Hash,
+    UpdatedAt,
+}
+
+pub fn credentials_table() -> Vec<TableStatement> {
+    let create = Table::create()
+        .table(Credentials::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Credentials::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(Credentials::User)
+                .integer()
+                .not_null()
+                .unique_key(),
+        )
+        .col(ColumnDef::new(Credentials::Hash).text().not_null())
+        .col(
+            ColumnDef::new(Credentials::UpdatedAt)
+                .date_time()
+                .not_null(),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_credentials_users")
+                .from(Credentials::Table, Credentials::User)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create)]
+}
+
+#[derive(Iden)]
+pub enum Totps {
+    Table,
+    Id,
+    User,
+    Secret,
+    LastStep,
+    EnabledAt,
+}
+
+#[derive(Iden)]
+pub enum RecoveryCodes {
+    Table,
+    Id,
+    User,
+    Hash,
+    UsedAt,
+}
+
+pub fn totps_table() -> Vec<TableStatement> {
+    let create = Table::create()
+        .table(Totps::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Totps::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(Totps::User)
+                .integer()
+                .not_null()
+                .unique_key(),
+        )
+        .col(ColumnDef::new(Totps::Secret).text().not_null())
+        .col(ColumnDef::new(Totps::LastStep).big_integer())
+        .col(ColumnDef::new(Totps::EnabledAt).date_time())
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_totps_users")
+                .from(Totps::Table, Totps::User)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create)]
+}
+
+pub fn recovery_codes_table() -> Vec<TableStatement> {
+    let create = Table::create()
+        .table(RecoveryCodes::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(RecoveryCodes::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(RecoveryCodes::User).integer().not_null())
+        .col(ColumnDef::new(RecoveryCodes::Hash).text().not_null())
+        .col(ColumnDef::new(RecoveryCodes::UsedAt).date_time())
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_recovery_codes_users")
+                .from(RecoveryCodes::Table, RecoveryCodes::User)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create)]
+}
+
+#[derive(Iden)]
+pub enum Nonces {
+    Table,
+    Id,
+    Access,
+    Nonce,
+    ExpiredAt,
+}
+
+pub fn nonces_table() -> Vec<TableStatement> {
+    let create = Table::create()
+        .table(Nonces::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Nonces::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Nonces::Access).text().not_null())
+        .col(ColumnDef::new(Nonces::Nonce).text().not_null())
+        .col(ColumnDef::new(Nonces::ExpiredAt).date_time().not_null())
+        .index(
+            Index::create()
+                .unique()
+                .name("uk_nonces_access_nonce")
+                .col(Nonces::Access)
+                .col(Nonces::Nonce),
+        )
         .to_owned();
 
     vec![TableStatement::Create(create)]
@@ -1,6 +1,13 @@
-use crate::{error::Result, model::key::KeyToCreate};
+use crate::{
+    error::Result,
+    model::{
+        key::{Key, KeyToCreate},
+        scope::Scope,
+    },
+};
+use chrono::{DateTime, Utc};
 use collie_core::repository::database::DbConnection;
-use sea_query::{Expr, Query, SqliteQueryBuilder};
+use sea_query::{Expr, Query, SimpleExpr, SqliteQueryBuilder};
 use sea_query_rusqlite::RusqliteBinder;
 
 use super::database::Keys;
@@ -9,16 +16,21 @@ pub fn create(conn: &DbConnection, arg: &KeyToCreate) -> Result<usize> {
     let (sql, values) = Query::insert()
         .into_table(Keys::Table)
         .columns([
+            Keys::User,
             Keys::Access,
             Keys::Secret,
             Keys::Description,
+            Keys::Scopes,
             Keys::ExpiredAt,
         ])
         .values_panic([
+            arg.user.into(),
             (*arg.access).into(),
             (*arg.secret).into(),
             arg.description.clone().into(),
-            arg.expired_at.into(),
+            arg.scopes.as_deref().map(Scope::join).into(),
+            // Stored in UTC, so that they compare as text with the current time.
+            arg.expired_at.map(|x| x.to_utc()).into(),
         ])
         .build_rusqlite(SqliteQueryBuilder);
 
@@ -26,17 +38,89 @@ pub fn create(conn: &DbConnection, arg: &KeyToCreate) -> Result<usize> {
     Ok(db.execute(sql.as_str(), &*values.as_params())?)
 }
 
-pub fn exists(conn: &DbConnection, access: &str, secret: &str) -> Result<bool> {
+pub fn exists(conn: &DbConnection, access: &str, secret: &str, now: DateTime<Utc>) -> Result<bool> {
+    Ok(read(conn, access, secret, now)?.is_some())
+}
+
+pub fn read(
+    conn: &DbConnection,
+    access: &str,
+    secret: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<Key>> {
     let (sql, values) = Query::select()
-        .columns([Keys::Id])
+        .columns([
+            Keys::Id,
+            Keys::User,
+            Keys::Access,
+            Keys::Secret,
+            Keys::Description,
+            Keys::Scopes,
+            Keys::ExpiredAt,
+        ])
         .from(Keys::Table)
         .and_where(Expr::col(Keys::Access).eq(access))
         .and_where(Expr::col(Keys::Secret).eq(secret))
-        .and_where(
-            Expr::col(Keys::ExpiredAt)
-                .gt(chrono::Utc::now())
-                .or(Expr::col(Keys::ExpiredAt).is_null()),
-        )
+        .and_where(not_expired(now))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Key::from))
+}
+
+pub fn read_by_access(
+    conn: &DbConnection,
+    access: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<Key>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Keys::Id,
+            Keys::User,
+            Keys::Access,
+            Keys::Secret,
+            Keys::Description,
+            Keys::Scopes,
+            Keys::ExpiredAt,
+        ])
+        .from(Keys::Table)
+        .and_where(Expr::col(Keys::Access).eq(access))
+        .and_where(not_expired(now))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Key::from))
+}
+
+pub fn read_id(conn: &DbConnection, access: &str) -> Result<Option<i32>> {
+    let (sql, values) = Query::select()
+        .columns([Keys::Id])
+        .from(Keys::Table)
+        .and_where(Expr::col(Keys::Access).eq(access))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(|row| row.get_unwrap(0)))
+}
+
+pub fn is_valid(conn: &DbConnection, id: i32, now: DateTime<Utc>) -> Result<bool> {
+    let (sql, values) = Query::select()
+        .columns([Keys::Id])
+        .from(Keys::Table)
+        .and_where(Expr::col(Keys::Id).eq(id))
+        .and_where(not_expired(now))
         .limit(1)
         .build_rusqlite(SqliteQueryBuilder);
 
@@ -46,3 +130,38 @@ pub fn exists(conn: &DbConnection, access: &str, secret: &str) -> Result<bool> {
 
     Ok(rows.next()?.is_some())
 }
+
+pub fn expire(conn: &DbConnection, access: &str, now: DateTime<Utc>) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Keys::Table)
+        .values([(Keys::ExpiredAt, now.into())])
+        .and_where(Expr::col(Keys::Access).eq(access))
+        .and_where(not_expired(now))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn update_secret(
+    conn: &DbConnection,
+    access: &str,
+    secret: &str,
+    now: DateTime<Utc>,
+) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Keys::Table)
+        .values([(Keys::Secret, secret.into())])
+        .and_where(Expr::col(Keys::Access).eq(access))
+        .and_where(not_expired(now))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+fn not_expired(now: DateTime<Utc>) -> SimpleExpr {
+    Expr::col(Keys::ExpiredAt)
+        .gt(now)
+        .or(Expr::col(Keys::ExpiredAt).is_null())
+}
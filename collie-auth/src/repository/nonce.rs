@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use collie_core::repository::database::DbConnection;
+use sea_query::{Expr, OnConflict, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{error::Result, model::signature::NonceToCreate};
+
+use super::database::Nonces;
+
+/// Returns 0 when the nonce has already been used with the key.
+pub fn create(conn: &DbConnection, arg: &NonceToCreate) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Nonces::Table)
+        .columns([Nonces::Access, Nonces::Nonce, Nonces::ExpiredAt])
+        .values_panic([
+            (*arg.access).into(),
+            (*arg.nonce).into(),
+            arg.expired_at.into(),
+        ])
+        .on_conflict(
+            OnConflict::columns([Nonces::Access, Nonces::Nonce])
+                .do_nothing()
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn delete_expired(conn: &DbConnection, now: DateTime<Utc>) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Nonces::Table)
+        .and_where(Expr::col(Nonces::ExpiredAt).lte(now))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
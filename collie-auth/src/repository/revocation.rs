@@ -0,0 +1,49 @@
+use chrono::Utc;
+use collie_core::repository::database::DbConnection;
+use sea_query::{Expr, OnConflict, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{error::Result, model::revocation::RevocationToCreate};
+
+use super::database::Revocations;
+
+pub fn create(conn: &DbConnection, arg: &RevocationToCreate) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Revocations::Table)
+        .columns([
+            Revocations::Jti,
+            Revocations::RevokedAt,
+            Revocations::ExpiredAt,
+        ])
+        .values_panic([(*arg.jti).into(), Utc::now().into(), arg.expired_at.into()])
+        .on_conflict(OnConflict::column(Revocations::Jti).do_nothing().to_owned())
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn exists(conn: &DbConnection, jti: &str) -> Result<bool> {
+    let (sql, values) = Query::select()
+        .columns([Revocations::Id])
+        .from(Revocations::Table)
+        .and_where(Expr::col(Revocations::Jti).eq(jti))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.is_some())
+}
+
+pub fn delete_expired(conn: &DbConnection) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Revocations::Table)
+        .and_where(Expr::col(Revocations::ExpiredAt).lte(Utc::now()))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
@@ -0,0 +1,143 @@
+use chrono::Utc;
+use collie_core::repository::database::DbConnection;
+use sea_query::{Expr, OnConflict, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{
+    error::Result,
+    model::totp::{Totp, TotpToCreate},
+};
+
+use super::database::{RecoveryCodes, Totps};
+
+/// Stores a new, not yet enabled secret for a user, replacing a pending enrollment.
+/// An enabled enrollment is left alone, and nothing is updated.
+pub fn upsert(conn: &DbConnection, arg: &TotpToCreate) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Totps::Table)
+        .columns([
+            Totps::User,
+            Totps::Secret,
+            Totps::LastStep,
+            Totps::EnabledAt,
+        ])
+        .values_panic([
+            arg.user.into(),
+            (*arg.secret).into(),
+            Option::<i64>::None.into(),
+            Option::<chrono::DateTime<Utc>>::None.into(),
+        ])
+        .on_conflict(
+            OnConflict::column(Totps::User)
+                .update_columns([Totps::Secret, Totps::LastStep, Totps::EnabledAt])
+                .action_and_where(Expr::col((Totps::Table, Totps::EnabledAt)).is_null())
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read(conn: &DbConnection, user: i32) -> Result<Option<Totp>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Totps::Id,
+            Totps::User,
+            Totps::Secret,
+            Totps::LastStep,
+            Totps::EnabledAt,
+        ])
+        .from(Totps::Table)
+        .and_where(Expr::col(Totps::User).eq(user))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Totp::from))
+}
+
+pub fn enable(conn: &DbConnection, user: i32, step: i64) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Totps::Table)
+        .values([
+            (Totps::EnabledAt, Utc::now().into()),
+            (Totps::LastStep, step.into()),
+        ])
+        .and_where(Expr::col(Totps::User).eq(user))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Moves the last used step forward, failing to update when the step was already used.
+pub fn update_last_step(conn: &DbConnection, user: i32, step: i64) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Totps::Table)
+        .values([(Totps::LastStep, step.into())])
+        .and_where(Expr::col(Totps::User).eq(user))
+        .and_where(
+            Expr::col(Totps::LastStep)
+                .lt(step)
+                .or(Expr::col(Totps::LastStep).is_null()),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn delete(conn: &DbConnection, user: i32) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Totps::Table)
+        .and_where(Expr::col(Totps::User).eq(user))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Replaces every recovery code of a user.
+pub fn create_recovery_codes(conn: &DbConnection, user: i32, hashes: &[String]) -> Result<usize> {
+    delete_recovery_codes(conn, user)?;
+
+    let mut query = Query::insert()
+        .into_table(RecoveryCodes::Table)
+        .columns([RecoveryCodes::User, RecoveryCodes::Hash])
+        .to_owned();
+    for hash in hashes {
+        query.values_panic([user.into(), hash.into()]);
+    }
+
+    let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Marks an unused recovery code as used, returning 1 if there was one.
+pub fn use_recovery_code(conn: &DbConnection, user: i32, hash: &str) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(RecoveryCodes::Table)
+        .values([(RecoveryCodes::UsedAt, Utc::now().into())])
+        .and_where(Expr::col(RecoveryCodes::User).eq(user))
+        .and_where(Expr::col(RecoveryCodes::Hash).eq(hash))
+        .and_where(Expr::col(RecoveryCodes::UsedAt).is_null())
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn delete_recovery_codes(conn: &DbConnection, user: i32) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(RecoveryCodes::Table)
+        .and_where(Expr::col(RecoveryCodes::User).eq(user))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
@@ -0,0 +1,101 @@
+use chrono::Duration;
+use collie_core::repository::database::DbConnection;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::model::attempt::{AttemptToCreate, Failures};
+use crate::repository::attempt;
+use crate::util::clock::{Clock, SystemClock};
+
+/// Limits failed logins per access key and per source (e.g. a client IP address).
+///
+/// Once `max_failures_per_key` or `max_failures_per_source` failures happen within `window`,
+/// further logins are refused until `lockout` has passed since the last failure.
+pub struct Throttle {
+    pub max_failures_per_key: u32,
+    pub max_failures_per_source: u32,
+    pub window: Duration,
+    pub lockout: Duration,
+    pub clock: Arc<dyn Clock>,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self {
+            max_failures_per_key: 5,
+            max_failures_per_source: 20,
+            window: Duration::minutes(15),
+            lockout: Duration::minutes(15),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+pub fn check(
+    conn: &DbConnection,
+    throttle: &Throttle,
+    access: &str,
+    source: Option<&str>,
+) -> Result<()> {
+    let since = throttle.clock.now() - throttle.window;
+
+    check_failures(
+        throttle,
+        attempt::count_by_access(conn, access, since)?,
+        throttle.max_failures_per_key,
+    )?;
+
+    if let Some(source) = source {
+        check_failures(
+            throttle,
+            attempt::count_by_source(conn, source, since)?,
+            throttle.max_failures_per_source,
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn fail(
+    conn: &DbConnection,
+    throttle: &Throttle,
+    access: &str,
+    source: Option<&str>,
+) -> Result<usize> {
+    attempt::create(
+        conn,
+        &AttemptToCreate {
+            access: access.to_string(),
+            source: source.map(ToString::to_string),
+            attempted_at: throttle.clock.now(),
+        },
+    )
+}
+
+pub fn succeed(conn: &DbConnection, access: &str) -> Result<usize> {
+    attempt::delete_by_access(conn, access)
+}
+
+/// Deletes failures that no longer count towards any lockout.
+pub fn prune(conn: &DbConnection, throttle: &Throttle) -> Result<usize> {
+    attempt::delete_before(
+        conn,
+        throttle.clock.now() - throttle.window.max(throttle.lockout),
+    )
+}
+
+fn check_failures(throttle: &Throttle, failures: Failures, max: u32) -> Result<()> {
+    if failures.count < max {
+        return Ok(());
+    }
+
+    let retry_after = failures.last_attempted_at.map_or(0, |x| {
+        (x + throttle.lockout - throttle.clock.now()).num_seconds()
+    });
+
+    if retry_after > 0 {
+        Err(Error::TooManyAttempts(retry_after))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::{Duration, Utc};
+use collie_core::repository::database::DbConnection;
+
+use crate::error::Result;
+use crate::model::audit::{Audit, AuditEvent, AuditOutcome, AuditReadOption, AuditToCreate};
+use crate::repository::audit;
+
+pub fn record(
+    conn: &DbConnection,
+    event: AuditEvent,
+    key: Option<i32>,
+    user: Option<i32>,
+    client: Option<&str>,
+    outcome: AuditOutcome,
+) -> Result<usize> {
+    audit::create(
+        conn,
+        &AuditToCreate {
+            event,
+            key,
+            user,
+            client: client.map(ToString::to_string),
+            outcome,
+        },
+    )
+}
+
+pub fn read_all(conn: &DbConnection, opt: &AuditReadOption) -> Result<Vec<Audit>> {
+    audit::read_all(conn, opt)
+}
+
+/// Deletes records older than `retention`.
+pub fn prune(conn: &DbConnection, retention: Duration) -> Result<usize> {
+    audit::delete_before(conn, Utc::now() - retention)
+}
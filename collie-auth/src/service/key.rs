@@ -1,24 +1,51 @@
+use chrono::Utc;
 use collie_core::repository::database::DbConnection;
 use rand::{thread_rng, Rng};
 
 use crate::error::Result;
+use crate::model::audit::{AuditEvent, AuditOutcome};
 use crate::model::key::KeyToCreate;
+use crate::model::scope::Scope;
 use crate::repository::key;
+use crate::service::audit;
+use crate::service::token::Cache;
 
-pub fn create(conn: DbConnection, description: Option<&str>) -> Result<(String, String)> {
+pub fn create(
+    conn: DbConnection,
+    user: i32,
+    description: Option<&str>,
+    scopes: Option<&[Scope]>,
+    client: Option<&str>,
+) -> Result<(String, String)> {
     let access_key = generate();
     let secret_key = generate();
 
-    let _ = key::create(
+    let created = key::create(
         &conn,
         &KeyToCreate {
+            user: Some(user),
             access: access_key.clone(),
             secret: secret_key.clone(),
             description: description.map(|x| x.to_string()),
+            scopes: scopes.map(<[Scope]>::to_vec),
             expired_at: None,
         },
     );
 
+    let (id, outcome) = match &created {
+        Ok(_) => (key::read_id(&conn, &access_key)?, AuditOutcome::Success),
+        Err(_) => (None, AuditOutcome::Failure),
+    };
+    audit::record(
+        &conn,
+        AuditEvent::KeyCreate,
+        id,
+        Some(user),
+        client,
+        outcome,
+    )?;
+    created?;
+
     Ok((access_key, secret_key))
 }
 
@@ -34,3 +61,64 @@ pub fn generate() -> String {
         })
         .collect()
 }
+
+pub fn revoke(
+    conn: &DbConnection,
+    access: &str,
+    cache: Option<&Cache>,
+    client: Option<&str>,
+) -> Result<usize> {
+    let revoked = key::expire(conn, access, Utc::now())?;
+    let id = key::read_id(conn, access)?;
+    invalidate(cache, id);
+
+    audit::record(
+        conn,
+        AuditEvent::KeyRevoke,
+        id,
+        None,
+        client,
+        outcome(revoked),
+    )?;
+
+    Ok(revoked)
+}
+
+/// Replaces the secret of a key that hasn't expired, returning the new secret.
+pub fn rotate(
+    conn: &DbConnection,
+    access: &str,
+    cache: Option<&Cache>,
+    client: Option<&str>,
+) -> Result<Option<String>> {
+    let secret_key = generate();
+    let rotated = key::update_secret(conn, access, &secret_key, Utc::now())?;
+    let id = key::read_id(conn, access)?;
+    invalidate(cache, id);
+
+    audit::record(
+        conn,
+        AuditEvent::KeyRotate,
+        id,
+        None,
+        client,
+        outcome(rotated),
+    )?;
+
+    Ok(if rotated > 0 { Some(secret_key) } else { None })
+}
+
+/// Drops cached verifications of tokens issued for the key, which need checking again.
+fn invalidate(cache: Option<&Cache>, id: Option<i32>) {
+    if let (Some(cache), Some(id)) = (cache, id) {
+        cache.invalidate_key(id);
+    }
+}
+
+fn outcome(affected: usize) -> AuditOutcome {
+    if affected > 0 {
+        AuditOutcome::Success
+    } else {
+        AuditOutcome::Failure
+    }
+}
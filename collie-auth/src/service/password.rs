@@ -0,0 +1,141 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use collie_core::{model::user::UserToCreate, repository::database::DbConnection, service::user};
+use std::sync::OnceLock;
+
+use crate::error::{Error, Result};
+use crate::model::audit::{AuditEvent, AuditOutcome};
+use crate::model::credential::CredentialToCreate;
+use crate::repository::credential;
+use crate::service::audit;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Verified against when no user has the name, so that unknown names take as long to
+/// reject as wrong passwords.
+static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+/// Creates a user who logs in with the given name and password. Returns the id of the user.
+pub fn register(
+    conn: &DbConnection,
+    name: &str,
+    password: &str,
+    client: Option<&str>,
+) -> Result<i32> {
+    let hash = hash(password)?;
+    let id = user::create(
+        conn,
+        &UserToCreate {
+            name: name.to_string(),
+        },
+    )?;
+
+    credential::upsert(conn, &CredentialToCreate { user: id, hash })?;
+    audit::record(
+        conn,
+        AuditEvent::UserRegister,
+        None,
+        Some(id),
+        client,
+        AuditOutcome::Success,
+    )?;
+
+    Ok(id)
+}
+
+/// Sets the password of an existing user, such as one who has only used keys so far.
+pub fn set(conn: &DbConnection, user: i32, password: &str, client: Option<&str>) -> Result<usize> {
+    let updated = credential::upsert(
+        conn,
+        &CredentialToCreate {
+            user,
+            hash: hash(password)?,
+        },
+    )?;
+
+    audit::record(
+        conn,
+        AuditEvent::PasswordChange,
+        None,
+        Some(user),
+        client,
+        AuditOutcome::Success,
+    )?;
+
+    Ok(updated)
+}
+
+pub fn change(
+    conn: &DbConnection,
+    user: i32,
+    current: &str,
+    password: &str,
+    client: Option<&str>,
+) -> Result<usize> {
+    let matches = credential::read(conn, user)?.is_some_and(|x| verify(&x.hash, current));
+    if !matches {
+        audit::record(
+            conn,
+            AuditEvent::PasswordChange,
+            None,
+            Some(user),
+            client,
+            AuditOutcome::Failure,
+        )?;
+        return Err(Error::Unauthorized);
+    }
+
+    set(conn, user, password, client)
+}
+
+/// Returns the id of the user if the password is correct.
+pub fn authenticate(conn: &DbConnection, name: &str, password: &str) -> Result<Option<i32>> {
+    match credential::read_by_name(conn, name)? {
+        Some(credential) => Ok(verify(&credential.hash, password).then_some(credential.user)),
+        None => {
+            verify(dummy_hash(), password);
+            Ok(None)
+        }
+    }
+}
+
+pub fn exists(conn: &DbConnection, user: i32) -> Result<bool> {
+    Ok(credential::read(conn, user)?.is_some())
+}
+
+/// Returns when the password of the user was last set, in microseconds since the epoch.
+pub fn changed_at(conn: &DbConnection, user: i32) -> Result<Option<i64>> {
+    Ok(credential::read(conn, user)?.map(|x| x.updated_at.timestamp_micros()))
+}
+
+fn hash(password: &str) -> Result<String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(Error::BadArgument);
+    }
+
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|x| x.to_string())
+        .map_err(|_| Error::PasswordHashingFailure)
+}
+
+fn dummy_hash() -> &'static str {
+    DUMMY_HASH.get_or_init(|| {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(b"collie-dummy-password", &salt)
+            .map(|x| x.to_string())
+            .unwrap_or_default()
+    })
+}
+
+fn verify(hash: &str, password: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
@@ -0,0 +1,124 @@
+use chrono::Duration;
+use collie_core::repository::database::DbConnection;
+use data_encoding::HEXLOWER_PERMISSIVE;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::model::signature::{NonceToCreate, SignedRequest};
+use crate::model::token::Claims;
+use crate::repository::{key, nonce};
+use crate::util::clock::{Clock, SystemClock};
+
+/// How far the timestamp of a signed request may be from the server's clock.
+/// Nonces are remembered for as long as their requests would be accepted.
+pub struct Freshness {
+    pub window: Duration,
+    pub clock: Arc<dyn Clock>,
+}
+
+impl Default for Freshness {
+    fn default() -> Self {
+        Self {
+            window: Duration::minutes(5),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+/// Signs a request the way clients are expected to, returning the hex-encoded signature of
+///
+/// ```text
+/// METHOD\npath\ntimestamp\nnonce\nhex(sha256(body))
+/// ```
+pub fn sign(
+    secret: &str,
+    method: &str,
+    path: &str,
+    timestamp: i64,
+    nonce: &str,
+    body: &[u8],
+) -> String {
+    let mac = mac(secret, method, path, timestamp, nonce, body);
+    HEXLOWER_PERMISSIVE.encode(&mac.finalize().into_bytes())
+}
+
+/// Checks a signed request against the key it claims to come from, and returns claims
+/// equivalent to those of a token issued for the key.
+pub fn verify(
+    conn: &DbConnection,
+    request: &SignedRequest,
+    freshness: &Freshness,
+) -> Result<Claims> {
+    let now = freshness.clock.now();
+    if (now.timestamp() - request.timestamp).abs() > freshness.window.num_seconds() {
+        return Err(Error::Unauthorized);
+    }
+
+    let key = key::read_by_access(conn, request.access, now)?.ok_or(Error::Unauthorized)?;
+    let signature = HEXLOWER_PERMISSIVE
+        .decode(request.signature.as_bytes())
+        .map_err(|_| Error::Unauthorized)?;
+
+    mac(
+        &key.secret,
+        request.method,
+        request.path,
+        request.timestamp,
+        request.nonce,
+        request.body,
+    )
+    .verify_slice(&signature)
+    .map_err(|_| Error::Unauthorized)?;
+
+    let stored = nonce::create(
+        conn,
+        &NonceToCreate {
+            access: request.access.to_string(),
+            nonce: request.nonce.to_string(),
+            expired_at: now + freshness.window * 2,
+        },
+    )?;
+    if stored == 0 {
+        return Err(Error::Unauthorized);
+    }
+
+    Ok(Claims {
+        jti: request.nonce.to_string(),
+        kid: Some(key.id),
+        user: key.user,
+        scopes: key.scopes,
+        iat: request.timestamp,
+        exp: request.timestamp + freshness.window.num_seconds(),
+        password_at: None,
+    })
+}
+
+/// Deletes nonces of requests that would be rejected as stale anyway.
+pub fn prune(conn: &DbConnection, freshness: &Freshness) -> Result<usize> {
+    nonce::delete_expired(conn, freshness.clock.now())
+}
+
+fn mac(
+    secret: &str,
+    method: &str,
+    path: &str,
+    timestamp: i64,
+    nonce: &str,
+    body: &[u8],
+) -> Hmac<Sha256> {
+    let body_hash = HEXLOWER_PERMISSIVE.encode(&Sha256::digest(body));
+    let canonical = format!(
+        "{}\n{}\n{}\n{}\n{}",
+        method.to_uppercase(),
+        path,
+        timestamp,
+        nonce,
+        body_hash
+    );
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(canonical.as_bytes());
+    mac
+}
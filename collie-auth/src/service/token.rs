@@ -1,48 +1,343 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use collie_core::repository::database::DbConnection;
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use crate::error::{Error, Result};
+use crate::model::audit::{AuditEvent, AuditOutcome};
+use crate::model::revocation::RevocationToCreate;
+use crate::model::scope::Scope;
 use crate::model::token::Claims;
-use crate::repository::key;
+use crate::repository::{key, revocation};
+use crate::service::attempt::{self, Throttle};
+use crate::service::totp::{self, Code};
+use crate::service::{audit, password};
 
-pub fn verify(access: &str, server_secret: &str) -> Result<bool> {
-    let validation = Validation::default();
-    match jsonwebtoken::decode::<Claims>(
-        access,
-        &DecodingKey::from_secret(server_secret.as_bytes()),
-        &validation,
-    ) {
-        Ok(token) => {
-            if token.claims.exp > Utc::now().timestamp() {
-                Ok(true)
-            } else {
-                Err(Error::Unauthorized)
+/// Remembers tokens that passed the database checks in [`verify`] for a short while,
+/// so that repeated requests with the same token don't hit the database every time.
+/// Entries are kept with the key the token was issued for, so that they can be dropped
+/// once the key is revoked or rotated.
+pub struct Cache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Option<i32>, Instant)>>,
+}
+
+impl Cache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn invalidate(&self, jti: &str) {
+        self.entries.lock().unwrap().remove(jti);
+    }
+
+    pub fn invalidate_key(&self, kid: i32) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, (x, _)| *x != Some(kid));
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn contains(&self, jti: &str) -> bool {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(jti) {
+            Some((_, until)) if *until > Instant::now() => true,
+            Some(_) => {
+                entries.remove(jti);
+                false
             }
+            None => false,
+        }
+    }
+
+    fn insert(&self, jti: &str, kid: Option<i32>) {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, (_, until)| *until > now);
+        entries.insert(jti.to_string(), (kid, now + self.ttl));
+    }
+}
+
+pub fn verify(
+    conn: &DbConnection,
+    access: &str,
+    server_secret: &str,
+    cache: Option<&Cache>,
+) -> Result<Claims> {
+    let claims = decode(access, server_secret)?;
+
+    if cache.is_some_and(|cache| cache.contains(&claims.jti)) {
+        return Ok(claims);
+    }
+
+    let is_valid = match (claims.kid, claims.user) {
+        (Some(kid), _) => key::is_valid(conn, kid, Utc::now())?,
+        (None, Some(user)) => {
+            claims.password_at.is_some() && password::changed_at(conn, user)? == claims.password_at
         }
-        Err(_) => Err(Error::Unauthorized),
+        (None, None) => false,
+    };
+
+    if !is_valid || revocation::exists(conn, &claims.jti)? {
+        return Err(Error::Unauthorized);
+    }
+
+    if let Some(cache) = cache {
+        cache.insert(&claims.jti, claims.kid);
     }
+
+    Ok(claims)
 }
 
+/// Issues a token for an access key. When the owner of the key has enrolled in TOTP,
+/// `code` must be a valid code for the time of its clock, or a recovery code.
 pub fn issue(
     conn: &DbConnection,
     access: &str,
     secret: &str,
+    code: Option<Code>,
+    source: Option<&str>,
+    server_secret: &str,
+    throttle: &Throttle,
+) -> Result<String> {
+    if let Err(e) = attempt::check(conn, throttle, access, source) {
+        let id = key::read_id(conn, access)?;
+        audit::record(
+            conn,
+            AuditEvent::TokenIssue,
+            id,
+            None,
+            source,
+            AuditOutcome::Locked,
+        )?;
+        return Err(e);
+    }
+
+    match key::read(conn, access, secret, throttle.clock.now())? {
+        Some(key) => {
+            if let Some(user) = key.user {
+                check_second_factor(conn, user, code, access, source, throttle)?;
+            }
+
+            attempt::succeed(conn, access)?;
+            audit::record(
+                conn,
+                AuditEvent::TokenIssue,
+                Some(key.id),
+                key.user,
+                source,
+                AuditOutcome::Success,
+            )?;
+            encode(Some(key.id), key.user, key.scopes, None, server_secret)
+        }
+        None => {
+            attempt::fail(conn, throttle, access, source)?;
+            let id = key::read_id(conn, access)?;
+            audit::record(
+                conn,
+                AuditEvent::TokenIssue,
+                id,
+                None,
+                source,
+                AuditOutcome::Failure,
+            )?;
+            Err(Error::Unauthorized)
+        }
+    }
+}
+
+/// Issues a token for a user logging in with a name and password, with every scope.
+/// Failed attempts are throttled by name, and TOTP is checked, the same way as in [`issue`].
+pub fn issue_with_password(
+    conn: &DbConnection,
+    name: &str,
+    password: &str,
+    code: Option<Code>,
+    source: Option<&str>,
     server_secret: &str,
+    throttle: &Throttle,
 ) -> Result<String> {
-    let exists = key::exists(conn, access, secret)?;
-    if exists {
-        Ok(encode(server_secret)?)
+    let identifier = password_identifier(name);
+    if let Err(e) = attempt::check(conn, throttle, &identifier, source) {
+        audit::record(
+            conn,
+            AuditEvent::TokenIssue,
+            None,
+            None,
+            source,
+            AuditOutcome::Locked,
+        )?;
+        return Err(e);
+    }
+
+    match password::authenticate(conn, name, password)? {
+        Some(user) => {
+            check_second_factor(conn, user, code, &identifier, source, throttle)?;
+
+            attempt::succeed(conn, &identifier)?;
+            audit::record(
+                conn,
+                AuditEvent::TokenIssue,
+                None,
+                Some(user),
+                source,
+                AuditOutcome::Success,
+            )?;
+            let password_at = password::changed_at(conn, user)?;
+            encode(
+                None,
+                Some(user),
+                Scope::ALL.to_vec(),
+                password_at,
+                server_secret,
+            )
+        }
+        None => {
+            attempt::fail(conn, throttle, &identifier, source)?;
+            audit::record(
+                conn,
+                AuditEvent::TokenIssue,
+                None,
+                None,
+                source,
+                AuditOutcome::Failure,
+            )?;
+            Err(Error::Unauthorized)
+        }
+    }
+}
+
+pub fn revoke(
+    conn: &DbConnection,
+    access: &str,
+    server_secret: &str,
+    cache: Option<&Cache>,
+    client: Option<&str>,
+) -> Result<usize> {
+    let claims = decode(access, server_secret)?;
+    let expired_at = DateTime::from_timestamp(claims.exp, 0)
+        .ok_or(Error::Unauthorized)?
+        .fixed_offset();
+
+    if let Some(cache) = cache {
+        cache.invalidate(&claims.jti);
+    }
+
+    let revoked = revocation::create(
+        conn,
+        &RevocationToCreate {
+            jti: claims.jti,
+            expired_at,
+        },
+    )?;
+
+    audit::record(
+        conn,
+        AuditEvent::TokenRevoke,
+        claims.kid,
+        claims.user,
+        client,
+        AuditOutcome::Success,
+    )?;
+
+    Ok(revoked)
+}
+
+/// Deletes revocations of tokens that have expired anyway.
+pub fn prune(conn: &DbConnection) -> Result<usize> {
+    revocation::delete_expired(conn)
+}
+
+/// Failed password logins are counted apart from failed access keys, so that a name
+/// given as an access key can't lock its user out, and vice versa.
+fn password_identifier(name: &str) -> String {
+    format!("user:{name}")
+}
+
+fn check_second_factor(
+    conn: &DbConnection,
+    user: i32,
+    code: Option<Code>,
+    identifier: &str,
+    source: Option<&str>,
+    throttle: &Throttle,
+) -> Result<()> {
+    if !totp::is_enrolled(conn, user)? {
+        return Ok(());
+    }
+
+    let Some(code) = code else {
+        return Err(Error::TotpRequired);
+    };
+
+    if totp::verify(conn, user, code.value, code.clock)? {
+        Ok(())
     } else {
+        attempt::fail(conn, throttle, identifier, source)?;
+        audit::record(
+            conn,
+            AuditEvent::TokenIssue,
+            None,
+            Some(user),
+            source,
+            AuditOutcome::Failure,
+        )?;
         Err(Error::Unauthorized)
     }
 }
 
-fn encode(secret: &str) -> Result<String> {
+fn decode(access: &str, server_secret: &str) -> Result<Claims> {
+    let validation = Validation::default();
+    match jsonwebtoken::decode::<Claims>(
+        access,
+        &DecodingKey::from_secret(server_secret.as_bytes()),
+        &validation,
+    ) {
+        Ok(token) if token.claims.exp > Utc::now().timestamp() => Ok(token.claims),
+        _ => Err(Error::Unauthorized),
+    }
+}
+
+pub fn require_scope(claims: &Claims, scope: Scope) -> Result<()> {
+    if claims.scopes.contains(&scope) {
+        Ok(())
+    } else {
+        Err(Error::Forbidden(scope.to_string()))
+    }
+}
+
+fn encode(
+    kid: Option<i32>,
+    user: Option<i32>,
+    scopes: Vec<Scope>,
+    password_at: Option<i64>,
+    secret: &str,
+) -> Result<String> {
     let now = Utc::now().timestamp();
     let claims = Claims {
+        jti: thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect(),
+        kid,
+        user,
+        scopes,
         iat: now,
         exp: now + 3600,
+        password_at,
     };
 
     jsonwebtoken::encode(
@@ -0,0 +1,176 @@
+use collie_core::repository::database::DbConnection;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, thread_rng, Rng, RngCore};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+use crate::model::totp::{Enrollment, TotpToCreate};
+use crate::repository::totp;
+use crate::util::clock::Clock;
+
+const PERIOD: i64 = 30;
+const DIGITS: u32 = 6;
+/// Number of steps before and after the current one that are still accepted.
+const SKEW: i64 = 1;
+const RECOVERY_CODES: usize = 10;
+
+/// A code given at login, checked against `clock` when it comes from an authenticator app.
+pub struct Code<'a> {
+    pub value: &'a str,
+    pub clock: &'a dyn Clock,
+}
+
+/// Starts enrollment with a new secret. It takes effect once [`confirm`]ed with a valid code.
+/// Users who have already enabled TOTP must [`disable`] it first.
+pub fn enroll(conn: &DbConnection, user: i32, issuer: &str, account: &str) -> Result<Enrollment> {
+    let mut bytes = [0u8; 20];
+    thread_rng().fill_bytes(&mut bytes);
+    let secret = BASE32_NOPAD.encode(&bytes);
+
+    let stored = totp::upsert(
+        conn,
+        &TotpToCreate {
+            user,
+            secret: secret.clone(),
+        },
+    )?;
+    if stored == 0 {
+        return Err(Error::BadArgument);
+    }
+
+    Ok(Enrollment {
+        uri: format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            encode_uri_component(issuer),
+            encode_uri_component(account),
+            secret,
+            encode_uri_component(issuer),
+            DIGITS,
+            PERIOD,
+        ),
+        secret,
+    })
+}
+
+/// Enables a pending enrollment and returns one-time recovery codes, which are only stored hashed.
+pub fn confirm(
+    conn: &DbConnection,
+    user: i32,
+    code: &str,
+    clock: &dyn Clock,
+) -> Result<Vec<String>> {
+    let totp = totp::read(conn, user)?.ok_or(Error::Unauthorized)?;
+    let step = match_step(&totp.secret, code, clock)?.ok_or(Error::Unauthorized)?;
+
+    totp::enable(conn, user, step)?;
+
+    let codes = (0..RECOVERY_CODES)
+        .map(|_| {
+            thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(10)
+                .map(|x| char::from(x).to_ascii_lowercase())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    totp::create_recovery_codes(
+        conn,
+        user,
+        &codes.iter().map(|x| hash(x)).collect::<Vec<_>>(),
+    )?;
+
+    Ok(codes)
+}
+
+/// Turns TOTP off, given a valid code or an unused recovery code.
+pub fn disable(conn: &DbConnection, user: i32, code: &str, clock: &dyn Clock) -> Result<usize> {
+    if !verify(conn, user, code, clock)? {
+        return Err(Error::Unauthorized);
+    }
+
+    totp::delete_recovery_codes(conn, user)?;
+    totp::delete(conn, user)
+}
+
+pub fn is_enrolled(conn: &DbConnection, user: i32) -> Result<bool> {
+    Ok(totp::read(conn, user)?.is_some_and(|x| x.enabled_at.is_some()))
+}
+
+/// Checks a code from an authenticator app, or else an unused recovery code.
+/// Each code is accepted only once.
+pub fn verify(conn: &DbConnection, user: i32, code: &str, clock: &dyn Clock) -> Result<bool> {
+    let Some(totp) = totp::read(conn, user)?.filter(|x| x.enabled_at.is_some()) else {
+        return Ok(false);
+    };
+
+    if let Some(step) = match_step(&totp.secret, code, clock)? {
+        return Ok(totp::update_last_step(conn, user, step)? > 0);
+    }
+
+    Ok(totp::use_recovery_code(conn, user, &hash(&normalize_recovery_code(code)))? > 0)
+}
+
+/// Generates the code for a base32 secret at the given unix time, as an authenticator app would.
+pub fn generate(secret: &str, timestamp: i64) -> Result<String> {
+    let key = BASE32_NOPAD
+        .decode(secret.as_bytes())
+        .map_err(|_| Error::BadArgument)?;
+    Ok(code_at(&key, timestamp.div_euclid(PERIOD)))
+}
+
+fn match_step(secret: &str, code: &str, clock: &dyn Clock) -> Result<Option<i64>> {
+    let key = BASE32_NOPAD
+        .decode(secret.as_bytes())
+        .map_err(|_| Error::BadArgument)?;
+    let current = clock.now().timestamp().div_euclid(PERIOD);
+
+    Ok((current - SKEW..=current + SKEW).find(|step| code_at(&key, *step) == code.trim()))
+}
+
+fn code_at(key: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).unwrap();
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+/// Recovery codes are generated in lowercase, and may be typed in any case or split by spaces.
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|x| !x.is_whitespace())
+        .map(|x| x.to_ascii_lowercase())
+        .collect()
+}
+
+fn hash(code: &str) -> String {
+    Sha256::digest(code.as_bytes())
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+fn encode_uri_component(x: &str) -> String {
+    x.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
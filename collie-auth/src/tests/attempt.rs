@@ -0,0 +1,125 @@
+use chrono::{DateTime, Duration};
+use std::sync::Arc;
+
+use super::common::{connection, user, SERVER_SECRET};
+use crate::error::Error;
+use crate::service::attempt::Throttle;
+use crate::service::{key, token};
+use crate::util::clock::FixedClock;
+
+fn throttle(clock: Arc<FixedClock>) -> Throttle {
+    Throttle {
+        max_failures_per_key: 3,
+        max_failures_per_source: 5,
+        window: Duration::minutes(10),
+        lockout: Duration::minutes(5),
+        clock,
+    }
+}
+
+fn clock() -> Arc<FixedClock> {
+    Arc::new(FixedClock::new(
+        DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00")
+            .unwrap()
+            .to_utc(),
+    ))
+}
+
+#[test]
+fn lock_out_key_until_lockout_expires() {
+    let conn = connection();
+    let (access, secret) = key::create(conn.clone(), user(&conn), None, None, None).unwrap();
+    let clock = clock();
+    let throttle = throttle(clock.clone());
+
+    for _ in 0..3 {
+        let result = token::issue(
+            &conn,
+            &access,
+            "wrong",
+            None,
+            None,
+            SERVER_SECRET,
+            &throttle,
+        );
+        assert!(matches!(result, Err(Error::Unauthorized)));
+        clock.advance(Duration::minutes(1));
+    }
+
+    let result = token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle,
+    );
+    assert!(matches!(result, Err(Error::TooManyAttempts(240))));
+
+    clock.advance(Duration::minutes(4));
+    assert!(token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle
+    )
+    .is_ok());
+
+    // A successful login starts over.
+    let result = token::issue(
+        &conn,
+        &access,
+        "wrong",
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle,
+    );
+    assert!(matches!(result, Err(Error::Unauthorized)));
+}
+
+#[test]
+fn lock_out_source_across_keys() {
+    let conn = connection();
+    let (access, secret) = key::create(conn.clone(), user(&conn), None, None, None).unwrap();
+    let clock = clock();
+    let throttle = throttle(clock.clone());
+
+    for i in 0..5 {
+        let result = token::issue(
+            &conn,
+            &format!("guess-{i}"),
+            "wrong",
+            None,
+            Some("10.0.0.1"),
+            SERVER_SECRET,
+            &throttle,
+        );
+        assert!(matches!(result, Err(Error::Unauthorized)));
+    }
+
+    let result = token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        Some("10.0.0.1"),
+        SERVER_SECRET,
+        &throttle,
+    );
+    assert!(matches!(result, Err(Error::TooManyAttempts(300))));
+    assert!(token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        Some("10.0.0.2"),
+        SERVER_SECRET,
+        &throttle
+    )
+    .is_ok());
+}
@@ -0,0 +1,101 @@
+use chrono::{Duration, Utc};
+
+use super::common::{connection, user, SERVER_SECRET};
+use crate::model::audit::{AuditEvent, AuditOutcome, AuditReadOption};
+use crate::service::attempt::Throttle;
+use crate::service::{audit, key, token};
+
+#[test]
+fn record_authentication_events() {
+    let conn = connection();
+    let throttle = Throttle::default();
+    let (access, secret) =
+        key::create(conn.clone(), user(&conn), None, None, Some("admin")).unwrap();
+
+    let _ = token::issue(
+        &conn,
+        &access,
+        "wrong",
+        None,
+        Some("10.0.0.1"),
+        SERVER_SECRET,
+        &throttle,
+    );
+    let _ = token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        Some("10.0.0.1"),
+        SERVER_SECRET,
+        &throttle,
+    );
+    key::rotate(&conn, &access, None, Some("admin"))
+        .unwrap()
+        .unwrap();
+    key::revoke(&conn, &access, None, Some("admin")).unwrap();
+
+    let audits = audit::read_all(&conn, &AuditReadOption::default()).unwrap();
+    let events = audits
+        .iter()
+        .rev()
+        .map(|x| (x.event, x.outcome, x.client.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (AuditEvent::KeyCreate, AuditOutcome::Success, Some("admin")),
+            (
+                AuditEvent::TokenIssue,
+                AuditOutcome::Failure,
+                Some("10.0.0.1")
+            ),
+            (
+                AuditEvent::TokenIssue,
+                AuditOutcome::Success,
+                Some("10.0.0.1")
+            ),
+            (AuditEvent::KeyRotate, AuditOutcome::Success, Some("admin")),
+            (AuditEvent::KeyRevoke, AuditOutcome::Success, Some("admin")),
+        ],
+        events
+    );
+    assert!(audits.iter().all(|x| x.key == Some(1)));
+
+    let issues = audit::read_all(
+        &conn,
+        &AuditReadOption {
+            events: Some(vec![AuditEvent::TokenIssue]),
+            since: Some((Utc::now() - Duration::minutes(1)).fixed_offset()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(2, issues.len());
+
+    let future = audit::read_all(
+        &conn,
+        &AuditReadOption {
+            since: Some((Utc::now() + Duration::minutes(1)).fixed_offset()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert!(future.is_empty());
+
+    assert_eq!(0, audit::prune(&conn, Duration::days(1)).unwrap());
+    assert_eq!(5, audit::prune(&conn, -Duration::minutes(1)).unwrap());
+}
+
+#[test]
+fn record_failed_key_creation() {
+    let conn = connection();
+    let user = user(&conn);
+    conn.lock().unwrap().execute("DROP TABLE keys", []).unwrap();
+
+    assert!(key::create(conn.clone(), user, None, None, Some("admin")).is_err());
+
+    let audits = audit::read_all(&conn, &AuditReadOption::default()).unwrap();
+    assert_eq!(1, audits.len());
+    assert_eq!(AuditEvent::KeyCreate, audits[0].event);
+    assert_eq!(AuditOutcome::Failure, audits[0].outcome);
+}
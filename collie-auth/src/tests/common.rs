@@ -0,0 +1,41 @@
+use collie_core::{
+    model::user::UserToCreate,
+    repository::database::{users_table, DbConnection, Migration},
+    service::user,
+};
+use rusqlite::Connection;
+use std::sync::{Arc, Mutex};
+
+use crate::repository::database::{
+    attempts_table, audits_table, credentials_table, keys_table, nonces_table,
+    recovery_codes_table, revocations_table, totps_table,
+};
+
+pub const SERVER_SECRET: &str = "server-secret";
+
+pub fn connection() -> DbConnection {
+    let db = Connection::open_in_memory().unwrap();
+    Migration::new()
+        .table(users_table())
+        .table(keys_table())
+        .table(revocations_table())
+        .table(attempts_table())
+        .table(audits_table())
+        .table(credentials_table())
+        .table(totps_table())
+        .table(recovery_codes_table())
+        .table(nonces_table())
+        .migrate(&db)
+        .unwrap();
+    Arc::new(Mutex::new(db))
+}
+
+pub fn user(conn: &DbConnection) -> i32 {
+    user::create(
+        conn,
+        &UserToCreate {
+            name: "collie".to_string(),
+        },
+    )
+    .unwrap()
+}
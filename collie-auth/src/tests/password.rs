@@ -0,0 +1,142 @@
+use super::common::{connection, SERVER_SECRET};
+use crate::error::Error;
+use crate::model::scope::Scope;
+use crate::service::attempt::Throttle;
+use crate::service::{password, token};
+
+#[test]
+fn log_in_with_password() {
+    let conn = connection();
+    let throttle = Throttle::default();
+    let user = password::register(&conn, "alice", "correct horse", None).unwrap();
+
+    let result = token::issue_with_password(
+        &conn,
+        "alice",
+        "wrong",
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle,
+    );
+    assert!(matches!(result, Err(Error::Unauthorized)));
+
+    let jwt = token::issue_with_password(
+        &conn,
+        "alice",
+        "correct horse",
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle,
+    )
+    .unwrap();
+    let claims = token::verify(&conn, &jwt, SERVER_SECRET, None).unwrap();
+    assert_eq!(Some(user), claims.user);
+    assert_eq!(None, claims.kid);
+    assert!(token::require_scope(&claims, Scope::FeedsWrite).is_ok());
+}
+
+#[test]
+fn reject_unknown_name() {
+    let conn = connection();
+    password::register(&conn, "alice", "correct horse", None).unwrap();
+
+    assert_eq!(
+        None,
+        password::authenticate(&conn, "bob", "correct horse").unwrap()
+    );
+    assert_eq!(None, password::authenticate(&conn, "bob", "wrong").unwrap());
+}
+
+#[test]
+fn change_password() {
+    let conn = connection();
+    let throttle = Throttle::default();
+    let user = password::register(&conn, "alice", "correct horse", None).unwrap();
+
+    assert!(password::change(&conn, user, "wrong", "battery staple", None).is_err());
+    assert!(matches!(
+        password::change(&conn, user, "correct horse", "short", None),
+        Err(Error::BadArgument)
+    ));
+    let jwt = token::issue_with_password(
+        &conn,
+        "alice",
+        "correct horse",
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle,
+    )
+    .unwrap();
+    password::change(&conn, user, "correct horse", "battery staple", None).unwrap();
+    assert!(matches!(
+        token::verify(&conn, &jwt, SERVER_SECRET, None),
+        Err(Error::Unauthorized)
+    ));
+
+    assert!(token::issue_with_password(
+        &conn,
+        "alice",
+        "correct horse",
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle
+    )
+    .is_err());
+    assert!(token::issue_with_password(
+        &conn,
+        "alice",
+        "battery staple",
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle
+    )
+    .is_ok());
+}
+
+#[test]
+fn throttle_password_apart_from_keys() {
+    let conn = connection();
+    let throttle = Throttle::default();
+    password::register(&conn, "alice", "correct horse", None).unwrap();
+
+    for _ in 0..throttle.max_failures_per_key {
+        let result = token::issue(
+            &conn,
+            "alice",
+            "wrong",
+            None,
+            None,
+            SERVER_SECRET,
+            &throttle,
+        );
+        assert!(matches!(result, Err(Error::Unauthorized)));
+    }
+    assert!(matches!(
+        token::issue(
+            &conn,
+            "alice",
+            "wrong",
+            None,
+            None,
+            SERVER_SECRET,
+            &throttle
+        ),
+        Err(Error::TooManyAttempts(_))
+    ));
+
+    assert!(token::issue_with_password(
+        &conn,
+        "alice",
+        "correct horse",
+        None,
+        None,
+        SERVER_SECRET,
+        &throttle
+    )
+    .is_ok());
+}
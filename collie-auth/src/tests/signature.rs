@@ -0,0 +1,62 @@
+use chrono::{DateTime, Duration};
+use std::sync::Arc;
+
+use super::common::{connection, user};
+use crate::model::scope::Scope;
+use crate::model::signature::SignedRequest;
+use crate::service::signature::{self, Freshness};
+use crate::service::{key, token};
+use crate::util::clock::{Clock, FixedClock};
+
+#[test]
+fn verify_signed_requests() {
+    let conn = connection();
+    let (access, secret) = key::create(
+        conn.clone(),
+        user(&conn),
+        None,
+        Some(&[Scope::ItemsWrite]),
+        None,
+    )
+    .unwrap();
+    let clock = Arc::new(FixedClock::new(
+        DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00")
+            .unwrap()
+            .to_utc(),
+    ));
+    let freshness = Freshness {
+        window: Duration::minutes(5),
+        clock: clock.clone(),
+    };
+
+    let body = br#"{"status":"Read"}"#;
+    let timestamp = clock.now().timestamp();
+    let signed = signature::sign(&secret, "PATCH", "/items/1", timestamp, "nonce-1", body);
+    let request = |nonce, body, signature| SignedRequest {
+        access: &access,
+        method: "PATCH",
+        path: "/items/1",
+        timestamp,
+        nonce,
+        body,
+        signature,
+    };
+
+    // A tampered body doesn't match the signature.
+    assert!(signature::verify(&conn, &request("nonce-1", b"{}", &signed), &freshness).is_err());
+
+    let claims = signature::verify(&conn, &request("nonce-1", body, &signed), &freshness).unwrap();
+    assert!(token::require_scope(&claims, Scope::ItemsWrite).is_ok());
+    assert!(token::require_scope(&claims, Scope::ItemsRead).is_err());
+
+    // The same nonce can't be replayed.
+    assert!(signature::verify(&conn, &request("nonce-1", body, &signed), &freshness).is_err());
+
+    // Stale requests are rejected.
+    let signed = signature::sign(&secret, "PATCH", "/items/1", timestamp, "nonce-2", body);
+    clock.advance(Duration::minutes(6));
+    assert!(signature::verify(&conn, &request("nonce-2", body, &signed), &freshness).is_err());
+
+    clock.advance(Duration::minutes(10));
+    assert_eq!(1, signature::prune(&conn, &freshness).unwrap());
+}
@@ -0,0 +1,165 @@
+use chrono::{FixedOffset, Utc};
+use std::time::Duration;
+
+use super::common::{connection, user, SERVER_SECRET};
+use crate::model::key::KeyToCreate;
+use crate::model::scope::Scope;
+use crate::repository;
+use crate::service::attempt::Throttle;
+use crate::service::{key, token};
+
+#[test]
+fn verify_issued_token() {
+    let conn = connection();
+    let (access, secret) = key::create(conn.clone(), user(&conn), None, None, None).unwrap();
+
+    let jwt = token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        None,
+        SERVER_SECRET,
+        &Throttle::default(),
+    )
+    .unwrap();
+    let claims = token::verify(&conn, &jwt, SERVER_SECRET, None).unwrap();
+    assert_eq!(claims.user, Some(1));
+    assert!(token::verify(&conn, &jwt, "another-secret", None).is_err());
+}
+
+#[test]
+fn reject_revoked_token() {
+    let conn = connection();
+    let (access, secret) = key::create(conn.clone(), user(&conn), None, None, None).unwrap();
+    let cache = token::Cache::new(Duration::from_secs(60));
+
+    let jwt = token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        None,
+        SERVER_SECRET,
+        &Throttle::default(),
+    )
+    .unwrap();
+    assert!(token::verify(&conn, &jwt, SERVER_SECRET, Some(&cache)).is_ok());
+
+    token::revoke(&conn, &jwt, SERVER_SECRET, Some(&cache), None).unwrap();
+    assert!(token::verify(&conn, &jwt, SERVER_SECRET, Some(&cache)).is_err());
+}
+
+#[test]
+fn reject_token_of_revoked_key() {
+    let conn = connection();
+    let (access, secret) = key::create(conn.clone(), user(&conn), None, None, None).unwrap();
+    let cache = token::Cache::new(Duration::from_secs(60));
+
+    let jwt = token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        None,
+        SERVER_SECRET,
+        &Throttle::default(),
+    )
+    .unwrap();
+    assert!(token::verify(&conn, &jwt, SERVER_SECRET, Some(&cache)).is_ok());
+    key::revoke(&conn, &access, Some(&cache), None).unwrap();
+
+    assert!(token::verify(&conn, &jwt, SERVER_SECRET, Some(&cache)).is_err());
+    assert!(token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        None,
+        SERVER_SECRET,
+        &Throttle::default()
+    )
+    .is_err());
+}
+
+#[test]
+fn carry_scopes_into_claims() {
+    let conn = connection();
+    let (access, secret) = key::create(
+        conn.clone(),
+        user(&conn),
+        None,
+        Some(&Scope::READ_ONLY),
+        None,
+    )
+    .unwrap();
+
+    let jwt = token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        None,
+        SERVER_SECRET,
+        &Throttle::default(),
+    )
+    .unwrap();
+    let claims = token::verify(&conn, &jwt, SERVER_SECRET, None).unwrap();
+
+    assert!(token::require_scope(&claims, Scope::ItemsRead).is_ok());
+    assert!(token::require_scope(&claims, Scope::ItemsWrite).is_err());
+}
+
+#[test]
+fn ignore_unknown_scopes() {
+    let conn = connection();
+    let (access, secret) = key::create(
+        conn.clone(),
+        user(&conn),
+        None,
+        Some(&Scope::READ_ONLY),
+        None,
+    )
+    .unwrap();
+    conn.lock()
+        .unwrap()
+        .execute("UPDATE keys SET scopes = 'items:read admin:all'", [])
+        .unwrap();
+
+    let jwt = token::issue(
+        &conn,
+        &access,
+        &secret,
+        None,
+        None,
+        SERVER_SECRET,
+        &Throttle::default(),
+    )
+    .unwrap();
+    let claims = token::verify(&conn, &jwt, SERVER_SECRET, None).unwrap();
+
+    assert_eq!(vec![Scope::ItemsRead], claims.scopes);
+}
+
+#[test]
+fn expire_keys_in_any_offset() {
+    let conn = connection();
+    let hawaii = FixedOffset::west_opt(10 * 3600).unwrap();
+    let expired_at = Utc::now() + chrono::Duration::hours(1);
+    repository::key::create(
+        &conn,
+        &KeyToCreate {
+            user: Some(user(&conn)),
+            access: "access".to_string(),
+            secret: "secret".to_string(),
+            description: None,
+            scopes: None,
+            expired_at: Some(expired_at.with_timezone(&hawaii)),
+        },
+    )
+    .unwrap();
+
+    let read = |now| repository::key::read(&conn, "access", "secret", now).unwrap();
+    assert!(read(Utc::now()).is_some());
+    assert!(read(expired_at + chrono::Duration::seconds(1)).is_none());
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Duration};
+use std::sync::Arc;
+
+use super::common::{connection, SERVER_SECRET};
+use crate::error::Error;
+use crate::service::attempt::Throttle;
+use crate::service::totp::{self, Code};
+use crate::service::{password, token};
+use crate::util::clock::{Clock, FixedClock};
+
+#[test]
+fn generate_rfc6238_codes() {
+    // "12345678901234567890" from the test vectors of RFC 6238
+    let secret = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+    assert_eq!("287082", totp::generate(secret, 59).unwrap());
+    assert_eq!("081804", totp::generate(secret, 1111111109).unwrap());
+    assert_eq!("005924", totp::generate(secret, 1234567890).unwrap());
+}
+
+#[test]
+fn require_code_once_enrolled() {
+    let conn = connection();
+    let clock = Arc::new(FixedClock::new(
+        DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00")
+            .unwrap()
+            .to_utc(),
+    ));
+    let throttle = Throttle::default();
+    let user = password::register(&conn, "alice", "correct horse", None).unwrap();
+    let login = |code: Option<&str>| {
+        token::issue_with_password(
+            &conn,
+            "alice",
+            "correct horse",
+            code.map(|value| Code {
+                value,
+                clock: clock.as_ref(),
+            }),
+            None,
+            SERVER_SECRET,
+            &throttle,
+        )
+    };
+
+    let enrollment = totp::enroll(&conn, user, "Collie", "alice@example.com").unwrap();
+    assert!(enrollment
+        .uri
+        .starts_with("otpauth://totp/Collie:alice%40example.com?secret="));
+    assert!(login(None).is_ok());
+
+    let code = |clock: &FixedClock| totp::generate(&enrollment.secret, clock.now().timestamp());
+    let recovery_codes =
+        totp::confirm(&conn, user, &code(&clock).unwrap(), clock.as_ref()).unwrap();
+    assert_eq!(10, recovery_codes.len());
+
+    assert!(matches!(login(None), Err(Error::TotpRequired)));
+    assert!(matches!(login(Some("000000")), Err(Error::Unauthorized)));
+
+    // The code used to confirm the enrollment can't be replayed.
+    assert!(matches!(
+        login(Some(&code(&clock).unwrap())),
+        Err(Error::Unauthorized)
+    ));
+
+    // A code from the previous step is still accepted.
+    let previous = code(&clock).unwrap();
+    clock.advance(Duration::seconds(30));
+    let current = code(&clock).unwrap();
+    clock.advance(Duration::seconds(30));
+    assert!(login(Some(&current)).is_ok());
+    assert!(login(Some(&previous)).is_err());
+
+    assert!(login(Some(&recovery_codes[0])).is_ok());
+    assert!(login(Some(&recovery_codes[0])).is_err());
+
+    // Recovery codes may be typed in uppercase and split by spaces.
+    let (head, tail) = recovery_codes[1].split_at(5);
+    assert!(login(Some(&format!(" {} {} ", head.to_uppercase(), tail))).is_ok());
+
+    // Enrolling again can't replace the enabled secret.
+    assert!(matches!(
+        totp::enroll(&conn, user, "Collie", "alice@example.com"),
+        Err(Error::BadArgument)
+    ));
+    assert!(totp::is_enrolled(&conn, user).unwrap());
+    assert!(matches!(login(None), Err(Error::TotpRequired)));
+
+    assert!(matches!(
+        totp::disable(&conn, user, "000000", clock.as_ref()),
+        Err(Error::Unauthorized)
+    ));
+    totp::disable(&conn, user, &code(&clock).unwrap(), clock.as_ref()).unwrap();
+    assert!(login(None).is_ok());
+}
@@ -14,6 +14,9 @@ pub enum Error {
     #[error("feed not found")]
     FeedNotFound,
 
+    #[error("invalid selector `{0}`")]
+    InvalidSelector(String),
+
     #[error(transparent)]
     RusqliteError {
         #[from]
@@ -32,6 +35,12 @@ pub enum Error {
         source: reqwest::Error,
     },
 
+    #[error(transparent)]
+    RegexError {
+        #[from]
+        source: regex::Error,
+    },
+
     #[error(transparent)]
     IoError {
         #[from]
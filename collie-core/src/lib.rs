@@ -1,22 +1,42 @@
 pub mod service {
     pub mod feed;
+    pub mod history;
+    pub mod icon;
     pub mod item;
+    pub mod retention;
+    pub mod rule;
+    pub mod tag;
+    pub mod user;
 }
 
 pub mod model {
     pub mod feed;
     pub mod item;
+    pub mod retention;
+    pub mod rule;
     pub mod syndication;
+    pub mod user;
 }
 
 pub mod repository {
     pub mod database;
     pub mod feed;
+    pub mod history;
+    pub mod icon;
     pub mod item;
+    pub mod retention;
+    pub mod rule;
+    pub mod tag;
+    pub mod user;
 }
 
 pub mod util {
+    pub mod extractor;
     pub mod fetcher;
+    pub mod link;
+    pub mod resolver;
+    pub mod sanitizer;
+    pub mod source;
 }
 
 pub mod worker;
@@ -25,5 +45,18 @@ pub mod error;
 
 #[cfg(test)]
 mod tests {
+    mod common;
+
+    mod extractor;
+    mod history;
+    mod icon;
+    mod item;
+    mod resolver;
+    mod retention;
+    mod rule;
+    mod sanitizer;
+    mod scrape;
+    mod source;
     mod syndication;
+    mod user;
 }
@@ -9,7 +9,7 @@ use std::{
 
 use crate::error::Error;
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub enum FeedStatus {
     Subscribed,
     Unsubscribed,
@@ -39,14 +39,53 @@ impl FromStr for FeedStatus {
     }
 }
 
+/// Where the items of a feed come from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum FeedKind {
+    /// An RSS or Atom feed.
+    Syndication,
+    /// A page without a feed, read with the selectors of the feed.
+    Scraped,
+    /// Read by the source registered under this name with the worker.
+    Custom(String),
+}
+
+impl Display for FeedKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            FeedKind::Syndication => write!(f, "syndication"),
+            FeedKind::Scraped => write!(f, "scraped"),
+            FeedKind::Custom(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+impl FromStr for FeedKind {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "syndication" => Ok(Self::Syndication),
+            "scraped" => Ok(Self::Scraped),
+            "" => Err(Error::InvalidEnumKey(x.to_string(), "FeedKind".to_string())),
+            _ => Ok(Self::Custom(x.to_string())),
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct Feed {
     pub id: i32,
     pub title: String,
     pub link: String,
+    pub kind: FeedKind,
     pub status: FeedStatus,
     pub checked_at: DateTime<FixedOffset>,
     pub fetch_old_items: bool,
+    /// Whether the article each item links to is fetched in place of its summary.
+    pub fetch_full_content: bool,
+    #[serde(flatten)]
+    pub metadata: FeedMetadata,
 }
 
 impl From<&Row<'_>> for Feed {
@@ -55,18 +94,92 @@ impl From<&Row<'_>> for Feed {
             id: row.get_unwrap("id"),
             title: row.get_unwrap("title"),
             link: row.get_unwrap("link"),
+            kind: FeedKind::from_str(&row.get_unwrap::<&str, String>("kind")).unwrap(),
             status: FeedStatus::from_str(&row.get_unwrap::<&str, String>("status")).unwrap(),
             checked_at: row.get_unwrap("checked_at"),
             fetch_old_items: row.get_unwrap("fetch_old_items"),
+            fetch_full_content: row.get_unwrap("fetch_full_content"),
+            metadata: FeedMetadata {
+                site_url: row.get_unwrap("site_url"),
+                description: row.get_unwrap("description"),
+                image: row.get_unwrap("image"),
+                language: row.get_unwrap("language"),
+                author: row.get_unwrap("author"),
+            },
+        }
+    }
+}
+
+/// What a feed tells about itself, refreshed whenever it is fetched.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FeedMetadata {
+    pub site_url: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub language: Option<String>,
+    pub author: Option<String>,
+}
+
+/// CSS selectors reading items out of the page of a scraped feed. Every selector but the
+/// container is matched within each container.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScrapeSelectors {
+    pub container: String,
+    pub title: String,
+    /// Read from `href`, or from the text when the element has none.
+    pub link: String,
+    /// Read from `datetime`, or from the text when the element has none.
+    pub date: Option<String>,
+    pub content: Option<String>,
+}
+
+impl From<&Row<'_>> for ScrapeSelectors {
+    fn from(row: &Row) -> Self {
+        Self {
+            container: row.get_unwrap("container"),
+            title: row.get_unwrap("title"),
+            link: row.get_unwrap("link"),
+            date: row.get_unwrap("date"),
+            content: row.get_unwrap("content"),
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl FeedFormat {
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime.trim().to_lowercase().as_str() {
+            "application/rss+xml" => Some(Self::Rss),
+            "application/atom+xml" => Some(Self::Atom),
+            // Plain JSON is declared by APIs too, such as the WordPress REST API.
+            "application/feed+json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A feed found while looking for the feeds of a page.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FeedCandidate {
+    pub link: String,
+    pub title: Option<String>,
+    pub format: FeedFormat,
+}
+
 #[derive(Deserialize)]
 pub struct FeedToCreate {
     pub title: String,
     pub link: String,
     pub fetch_old_items: bool,
+    /// Makes a scraped feed of the page at the link rather than looking for its feed.
+    #[serde(default)]
+    pub selectors: Option<ScrapeSelectors>,
 }
 
 #[derive(Deserialize)]
@@ -77,4 +190,9 @@ pub struct FeedToUpdate {
     pub status: Option<FeedStatus>,
     pub checked_at: Option<DateTime<FixedOffset>>,
     pub fetch_old_items: Option<bool>,
+    #[serde(default)]
+    pub fetch_full_content: Option<bool>,
+    /// Replaces the selectors of a scraped feed. Ignored for other feeds.
+    #[serde(default)]
+    pub selectors: Option<ScrapeSelectors>,
 }
@@ -1,4 +1,4 @@
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, NaiveDate};
 use core::fmt::{self, Display, Formatter};
 use rusqlite::Row;
 use serde::{Deserialize, Serialize};
@@ -6,8 +6,9 @@ use sha1_smol::Sha1;
 use std::str::FromStr;
 
 use crate::error::Error;
+use crate::model::feed::FeedStatus;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub enum ItemStatus {
     Unread,
     Read,
@@ -51,10 +52,19 @@ pub struct Item {
     pub author: Option<String>,
     pub title: String,
     pub description: String,
+    /// The full article, for feeds that fetch it.
+    pub content: Option<String>,
     pub link: String,
     pub status: ItemStatus,
     pub is_saved: bool,
     pub published_at: DateTime<FixedOffset>,
+    /// When the item was stored, unknown for items stored before this was recorded until
+    /// the migration backfills it.
+    pub received_at: Option<DateTime<FixedOffset>>,
+    /// When the item was marked read, unless it is unread.
+    pub read_at: Option<DateTime<FixedOffset>>,
+    /// When the item was saved, unless it isn't.
+    pub saved_at: Option<DateTime<FixedOffset>>,
     pub feed: ItemFeed,
 }
 
@@ -66,10 +76,14 @@ impl From<&Row<'_>> for Item {
             author: row.get_unwrap("author"),
             title: row.get_unwrap("title"),
             description: row.get_unwrap("description"),
+            content: row.get_unwrap("content"),
             link: row.get_unwrap("link"),
             status: ItemStatus::from_str(&row.get_unwrap::<&str, String>("status")).unwrap(),
             is_saved: row.get_unwrap("is_saved"),
             published_at: row.get_unwrap("published_at"),
+            received_at: row.get_unwrap("received_at"),
+            read_at: row.get_unwrap("read_at"),
+            saved_at: row.get_unwrap("saved_at"),
             feed: ItemFeed {
                 id: row.get_unwrap("feed_id"),
                 title: row.get_unwrap("feed_title"),
@@ -79,20 +93,79 @@ impl From<&Row<'_>> for Item {
     }
 }
 
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct ItemCounts {
+    pub unread: i64,
+    pub saved: i64,
+    pub total: i64,
+}
+
+impl From<&Row<'_>> for ItemCounts {
+    fn from(row: &Row) -> Self {
+        Self {
+            unread: row.get_unwrap("unread"),
+            saved: row.get_unwrap("saved"),
+            total: row.get_unwrap("total"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct FeedItemCounts {
+    pub feed: i32,
+    #[serde(flatten)]
+    pub counts: ItemCounts,
+}
+
+impl From<&Row<'_>> for FeedItemCounts {
+    fn from(row: &Row) -> Self {
+        Self {
+            feed: row.get_unwrap("feed"),
+            counts: ItemCounts::from(row),
+        }
+    }
+}
+
+/// Counts of the items published on a day, in UTC.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DailyItemCounts {
+    pub day: NaiveDate,
+    #[serde(flatten)]
+    pub counts: ItemCounts,
+}
+
+impl From<&Row<'_>> for DailyItemCounts {
+    fn from(row: &Row) -> Self {
+        Self {
+            day: row.get_unwrap("day"),
+            counts: ItemCounts::from(row),
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ItemToCreate {
     pub author: Option<String>,
     pub title: String,
     pub description: String,
+    #[serde(default)]
+    pub content: Option<String>,
     pub link: String,
+    /// The link as the feed gave it, which identifies the item when `link` was rewritten
+    /// from it. Defaults to `link`.
+    #[serde(default)]
+    pub source_link: Option<String>,
     pub status: ItemStatus,
+    #[serde(default)]
+    pub is_saved: bool,
     pub published_at: DateTime<FixedOffset>,
     pub feed: i32,
 }
 
 impl ItemToCreate {
     pub fn fingerprint(&self) -> String {
-        Sha1::from(format!("{}:{}", &self.title, &self.link)).hexdigest()
+        let link = self.source_link.as_deref().unwrap_or(&self.link);
+        Sha1::from(format!("{}:{}", &self.title, link)).hexdigest()
     }
 }
 
@@ -110,20 +183,117 @@ pub struct ItemToUpdateAll {
     pub opt: Option<ItemReadOption>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
 pub enum ItemOrder {
+    /// The most recently received first, with items of unknown receipt last.
+    #[default]
     ReceivedDateDesc,
     PublishedDateDesc,
     UnreadFirst,
+    /// The most recently read first, with unread items last.
+    ReadDateDesc,
+    /// The most recently saved first, with unsaved items last.
+    SavedDateDesc,
 }
 
-#[derive(Deserialize)]
+/// The position of an item in a list, which stays put while items are inserted. Clients
+/// only pass around its encoded form.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ItemCursor {
+    pub id: i32,
+    pub status: ItemStatus,
+    pub published_at: DateTime<FixedOffset>,
+    pub received_at: Option<DateTime<FixedOffset>>,
+    pub read_at: Option<DateTime<FixedOffset>>,
+    pub saved_at: Option<DateTime<FixedOffset>>,
+}
+
+impl From<&Item> for ItemCursor {
+    fn from(item: &Item) -> Self {
+        Self {
+            id: item.id,
+            status: item.status,
+            published_at: item.published_at,
+            received_at: item.received_at,
+            read_at: item.read_at,
+            saved_at: item.saved_at,
+        }
+    }
+}
+
+impl Display for ItemCursor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let json = serde_json::to_vec(self).map_err(|_| fmt::Error)?;
+        for byte in json {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ItemCursor {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        let bytes = (0..x.len())
+            .step_by(2)
+            .map(|i| x.get(i..i + 2).and_then(|x| u8::from_str_radix(x, 16).ok()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::BadArgument)?;
+
+        serde_json::from_slice(&bytes).map_err(|_| Error::BadArgument)
+    }
+}
+
+/// A page of items with the cursors leading to its neighbors, which are `None` at either
+/// end of the list.
+#[derive(Serialize, Debug)]
+pub struct ItemPage {
+    pub items: Vec<Item>,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+}
+
+/// The items changed by one bulk update, which can be undone with its id.
+#[derive(Serialize, Debug)]
+pub struct ItemBatch {
+    pub id: String,
+    pub count: usize,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
 pub struct ItemReadOption {
     pub ids: Option<Vec<i32>>,
     pub feed: Option<i32>,
+    /// Items of any of these feeds, or of every feed when empty.
+    #[serde(default)]
+    pub feeds: Vec<i32>,
+    #[serde(default)]
+    pub exclude_feeds: Vec<i32>,
     pub status: Option<ItemStatus>,
     pub is_saved: Option<bool>,
+    /// Items published at this time or later.
+    #[serde(default)]
+    pub published_after: Option<DateTime<FixedOffset>>,
+    /// Items published before this time, so that consecutive ranges don't overlap.
+    #[serde(default)]
+    pub published_before: Option<DateTime<FixedOffset>>,
+    /// Items whose author contains this text, ignoring case.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Items of feeds with this status, such as hiding those of unsubscribed feeds.
+    #[serde(default)]
+    pub feed_status: Option<FeedStatus>,
+    /// Applied to every read, defaulting to the most recently received first.
     pub order_by: Option<ItemOrder>,
     pub limit: Option<u64>,
-    pub offset: Option<u64>,
+    /// Reads the items right before the one with this cursor.
+    #[serde(default)]
+    pub before: Option<String>,
+    /// Reads the items right after the one with this cursor.
+    #[serde(default)]
+    pub after: Option<String>,
+    /// Sanitizes descriptions with the default policy, for items stored verbatim.
+    #[serde(default)]
+    pub sanitize: bool,
 }
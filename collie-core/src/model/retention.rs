@@ -0,0 +1,44 @@
+use chrono::Duration;
+use rusqlite::Row;
+use serde::{Deserialize, Serialize};
+
+/// Which read items to delete. Saved items are always kept.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RetentionPolicy {
+    /// Items published longer ago than this many seconds are deleted.
+    pub max_age: Option<i64>,
+    /// Only this many of the most recently published items are kept per feed.
+    pub max_count: Option<u64>,
+    /// Whether unread items may be deleted too.
+    pub delete_unread: bool,
+}
+
+impl RetentionPolicy {
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age.map(Duration::seconds)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Retention {
+    pub feed: i32,
+    pub policy: RetentionPolicy,
+}
+
+impl From<&Row<'_>> for Retention {
+    fn from(row: &Row) -> Self {
+        Self {
+            feed: row.get_unwrap("feed"),
+            policy: RetentionPolicy {
+                max_age: row.get_unwrap("max_age"),
+                max_count: row.get_unwrap("max_count"),
+                delete_unread: row.get_unwrap("delete_unread"),
+            },
+        }
+    }
+}
+
+pub struct PurgeToCreate {
+    pub feed: i32,
+    pub fingerprint: String,
+}
@@ -0,0 +1,175 @@
+use chrono::{DateTime, FixedOffset};
+use core::fmt;
+use rusqlite::Row;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crate::error::Error;
+
+use super::item::{Item, ItemToCreate};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RuleField {
+    Title,
+    Author,
+    Link,
+    Description,
+}
+
+impl Display for RuleField {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Title => write!(f, "title"),
+            Self::Author => write!(f, "author"),
+            Self::Link => write!(f, "link"),
+            Self::Description => write!(f, "description"),
+        }
+    }
+}
+
+impl FromStr for RuleField {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "title" => Ok(Self::Title),
+            "author" => Ok(Self::Author),
+            "link" => Ok(Self::Link),
+            "description" => Ok(Self::Description),
+            _ => Err(Error::InvalidEnumKey(
+                x.to_string(),
+                "RuleField".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RuleAction {
+    Skip,
+    MarkRead,
+    Save,
+    Tag,
+}
+
+impl Display for RuleAction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Skip => write!(f, "skip"),
+            Self::MarkRead => write!(f, "mark_read"),
+            Self::Save => write!(f, "save"),
+            Self::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+impl FromStr for RuleAction {
+    type Err = Error;
+
+    fn from_str(x: &str) -> std::result::Result<Self, Self::Err> {
+        match x {
+            "skip" => Ok(Self::Skip),
+            "mark_read" => Ok(Self::MarkRead),
+            "save" => Ok(Self::Save),
+            "tag" => Ok(Self::Tag),
+            _ => Err(Error::InvalidEnumKey(
+                x.to_string(),
+                "RuleAction".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Rule {
+    pub id: i32,
+    /// Rules without a feed apply to every feed of the user.
+    pub feed: Option<i32>,
+    pub field: RuleField,
+    pub pattern: String,
+    pub is_regex: bool,
+    pub action: RuleAction,
+    pub tag: Option<String>,
+    pub matches: i64,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl From<&Row<'_>> for Rule {
+    fn from(row: &Row) -> Self {
+        Self {
+            id: row.get_unwrap("id"),
+            feed: row.get_unwrap("feed"),
+            field: RuleField::from_str(&row.get_unwrap::<&str, String>("field")).unwrap(),
+            pattern: row.get_unwrap("pattern"),
+            is_regex: row.get_unwrap("is_regex"),
+            action: RuleAction::from_str(&row.get_unwrap::<&str, String>("action")).unwrap(),
+            tag: row.get_unwrap("tag"),
+            matches: row.get_unwrap("matches"),
+            created_at: row.get_unwrap("created_at"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RuleToCreate {
+    pub feed: Option<i32>,
+    pub field: RuleField,
+    pub pattern: String,
+    pub is_regex: bool,
+    pub action: RuleAction,
+    pub tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct RuleToUpdate {
+    pub id: i32,
+    pub field: Option<RuleField>,
+    pub pattern: Option<String>,
+    pub is_regex: Option<bool>,
+    pub action: Option<RuleAction>,
+    pub tag: Option<String>,
+}
+
+/// The fields of an item that rules can match against.
+pub struct RuleSubject<'a> {
+    pub title: &'a str,
+    pub author: Option<&'a str>,
+    pub link: &'a str,
+    pub description: &'a str,
+}
+
+impl RuleSubject<'_> {
+    pub fn get(&self, field: RuleField) -> &str {
+        match field {
+            RuleField::Title => self.title,
+            RuleField::Author => self.author.unwrap_or_default(),
+            RuleField::Link => self.link,
+            RuleField::Description => self.description,
+        }
+    }
+}
+
+impl<'a> From<&'a ItemToCreate> for RuleSubject<'a> {
+    fn from(item: &'a ItemToCreate) -> Self {
+        Self {
+            title: &item.title,
+            author: item.author.as_deref(),
+            link: &item.link,
+            description: &item.description,
+        }
+    }
+}
+
+impl<'a> From<&'a Item> for RuleSubject<'a> {
+    fn from(item: &'a Item) -> Self {
+        Self {
+            title: &item.title,
+            author: item.author.as_deref(),
+            link: &item.link,
+            description: &item.description,
+        }
+    }
+}
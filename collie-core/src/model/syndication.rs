@@ -8,12 +8,16 @@ pub struct RawItem {
     pub title: String,
     pub author: Option<String>,
     pub link: Option<String>,
+    /// The link as written in the feed, when `link` was resolved from it, which keeps
+    /// identifying the item.
+    pub source_link: Option<String>,
     pub content: Option<String>,
     pub published_at: Option<DateTime<FixedOffset>>,
 }
 
 // borrowed from https://github.com/rust-syndication/syndication
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone)]
 pub enum Feed {
     Atom(atom_syndication::Feed),
@@ -0,0 +1,25 @@
+use chrono::{DateTime, FixedOffset};
+use rusqlite::Row;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Debug)]
+pub struct User {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl From<&Row<'_>> for User {
+    fn from(row: &Row) -> Self {
+        Self {
+            id: row.get_unwrap("id"),
+            name: row.get_unwrap("name"),
+            created_at: row.get_unwrap("created_at"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UserToCreate {
+    pub name: String,
+}
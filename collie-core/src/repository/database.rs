@@ -1,7 +1,7 @@
 use rusqlite::Connection as RusqliteConnection;
 use sea_query::{
-    ColumnDef, Expr, ForeignKey, ForeignKeyAction, Iden, Index, SqliteQueryBuilder, Table,
-    TableStatement,
+    ColumnDef, Expr, ForeignKey, ForeignKeyAction, Iden, Index, IndexCreateStatement, Query,
+    SqliteQueryBuilder, Table, TableStatement, UpdateStatement,
 };
 use std::{
     path::Path,
@@ -12,34 +12,152 @@ use crate::error::Result;
 
 pub type DbConnection = Arc<Mutex<RusqliteConnection>>;
 
+#[derive(Iden)]
+pub enum Users {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+}
+
 #[derive(Iden)]
 pub enum Feeds {
     Table,
     Id,
+    User,
     Title,
     Link,
     Status,
     CheckedAt,
     FetchOldItems,
+    SiteUrl,
+    Description,
+    Image,
+    Language,
+    Author,
+    FetchFullContent,
+    Kind,
+    SeenMark,
 }
 
 #[derive(Iden)]
 pub enum Items {
     Table,
     Id,
+    User,
     Fingerprint,
     Author,
     Title,
     Description,
+    Content,
     Link,
     Status,
     IsSaved,
     PublishedAt,
     Feed,
+    ReceivedAt,
+    ReadAt,
+    SavedAt,
+}
+
+#[derive(Iden)]
+pub enum ReadHistory {
+    Table,
+    Id,
+    User,
+    Item,
+    Batch,
+    Status,
+    PreviousReadAt,
+    ChangedAt,
+}
+
+#[derive(Iden)]
+pub enum Retentions {
+    Table,
+    Id,
+    Feed,
+    MaxAge,
+    MaxCount,
+    DeleteUnread,
+}
+
+#[derive(Iden)]
+pub enum Purges {
+    Table,
+    Id,
+    User,
+    Feed,
+    Fingerprint,
+    PurgedAt,
+}
+
+#[derive(Iden)]
+pub enum Rules {
+    Table,
+    Id,
+    User,
+    Feed,
+    Field,
+    Pattern,
+    IsRegex,
+    Action,
+    Tag,
+    Matches,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+pub enum RuleSkips {
+    Table,
+    Id,
+    Rule,
+    Feed,
+    Fingerprint,
+}
+
+#[derive(Iden)]
+pub enum Tags {
+    Table,
+    Id,
+    Item,
+    Name,
+}
+
+#[derive(Iden)]
+pub enum Icons {
+    Table,
+    Id,
+    Hash,
+    Mime,
+    Data,
+}
+
+#[derive(Iden)]
+pub enum FeedSelectors {
+    Table,
+    Id,
+    Feed,
+    Container,
+    Title,
+    Link,
+    Date,
+    Content,
+}
+
+#[derive(Iden)]
+pub enum FeedIcons {
+    Table,
+    Id,
+    Feed,
+    Icon,
+    CheckedAt,
 }
 
 pub struct Migration {
     tables: Vec<Vec<TableStatement>>,
+    backfills: Vec<Vec<UpdateStatement>>,
+    indexes: Vec<Vec<IndexCreateStatement>>,
 }
 
 impl Default for Migration {
@@ -50,7 +168,11 @@ impl Default for Migration {
 
 impl Migration {
     pub fn new() -> Self {
-        Self { tables: Vec::new() }
+        Self {
+            tables: Vec::new(),
+            backfills: Vec::new(),
+            indexes: Vec::new(),
+        }
     }
 
     pub fn table(mut self, stmts: Vec<TableStatement>) -> Self {
@@ -58,6 +180,20 @@ impl Migration {
         self
     }
 
+    /// Adds updates filling in columns of rows that existed before the columns were added.
+    /// They run after every table and before any index.
+    pub fn backfill(mut self, stmts: Vec<UpdateStatement>) -> Self {
+        self.backfills.push(stmts);
+        self
+    }
+
+    /// Adds indexes, which are created after every table so that they can cover columns
+    /// added by later statements.
+    pub fn index(mut self, stmts: Vec<IndexCreateStatement>) -> Self {
+        self.indexes.push(stmts);
+        self
+    }
+
     pub fn migrate(&self, db: &RusqliteConnection) -> Result<()> {
         let sql = self
             .tables
@@ -67,12 +203,25 @@ impl Migration {
                     .iter()
                     .map(|stmt| stmt.build(SqliteQueryBuilder))
                     .collect::<Vec<_>>()
-                    .join(";")
             })
+            .chain(self.backfills.iter().map(|stmts| {
+                stmts
+                    .iter()
+                    .map(|stmt| stmt.to_string(SqliteQueryBuilder))
+                    .collect::<Vec<_>>()
+            }))
+            .chain(self.indexes.iter().map(|stmts| {
+                stmts
+                    .iter()
+                    .map(|stmt| stmt.build(SqliteQueryBuilder))
+                    .collect::<Vec<_>>()
+            }))
             .collect::<Vec<_>>();
 
-        for stmt in sql {
-            let _ = db.execute_batch(&stmt);
+        for stmts in sql {
+            for stmt in stmts {
+                let _ = db.execute(&stmt, []);
+            }
         }
 
         Ok(())
@@ -83,6 +232,111 @@ pub fn open_connection(path: &Path) -> Result<RusqliteConnection> {
     Ok(RusqliteConnection::open(path)?)
 }
 
+/// Tells whether a table exists, for features whose tables embedders may not migrate.
+pub fn has_table(db: &RusqliteConnection, table: &str) -> bool {
+    db.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |_| Ok(()),
+    )
+    .is_ok()
+}
+
+/// Databases created before users existed keep feeds unique by title and link, and items
+/// unique by fingerprint, across the whole file. This recreates both tables with per-user
+/// constraints while keeping their rows and indexes, and does nothing for databases that
+/// are up to date. It can run before or after [`Migration::migrate`].
+///
+/// The rows kept have no user, and the worker doesn't poll feeds without one, so their
+/// feeds stay idle until [`crate::service::user::adopt`] gives them to a user.
+pub fn rebuild_legacy_tables(db: &RusqliteConnection) -> Result<()> {
+    let is_legacy = |table: &str, constraint: &str| -> Result<bool> {
+        let sql: Option<String> = db
+            .query_row(
+                "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(sql.is_some_and(|x| x.contains(constraint)))
+    };
+
+    let feeds = is_legacy("feeds", "\"uk_feeds_title_link\"")?;
+    let items = is_legacy("items", "\"fingerprint\" text NOT NULL UNIQUE")?;
+    if !feeds && !items {
+        return Ok(());
+    }
+
+    db.execute_batch("PRAGMA foreign_keys = OFF; PRAGMA legacy_alter_table = ON; BEGIN;")?;
+
+    let rebuild = |table: &str,
+                   stmts: Vec<TableStatement>,
+                   indexes: Vec<IndexCreateStatement>|
+     -> Result<()> {
+        let columns = {
+            let stmt = db.prepare(&format!("SELECT * FROM \"{table}\" LIMIT 0"))?;
+            stmt.column_names()
+                .iter()
+                .map(|x| format!("\"{x}\""))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        db.execute_batch(&format!(
+            "ALTER TABLE \"{table}\" RENAME TO \"{table}_legacy\""
+        ))?;
+        for stmt in stmts {
+            let _ = db.execute(&stmt.build(SqliteQueryBuilder), []);
+        }
+        db.execute_batch(&format!(
+            "INSERT INTO \"{table}\" ({columns}) SELECT {columns} FROM \"{table}_legacy\"; \
+             DROP TABLE \"{table}_legacy\";"
+        ))?;
+        // Indexes went away with the legacy table.
+        for stmt in indexes {
+            db.execute(&stmt.build(SqliteQueryBuilder), [])?;
+        }
+
+        Ok(())
+    };
+
+    let result = (|| {
+        if feeds {
+            rebuild("feeds", feeds_table(), vec![])?;
+        }
+        if items {
+            rebuild("items", items_table(), items_indexes())?;
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => db.execute_batch("COMMIT;")?,
+        Err(_) => db.execute_batch("ROLLBACK;")?,
+    }
+    db.execute_batch("PRAGMA legacy_alter_table = OFF; PRAGMA foreign_keys = ON;")?;
+
+    result
+}
+
+pub fn users_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(Users::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Users::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Users::Name).text().not_null().unique_key())
+        .col(ColumnDef::new(Users::CreatedAt).date_time().not_null())
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
 pub fn feeds_table() -> Vec<TableStatement> {
     let create_stmt = Table::create()
         .table(Feeds::Table)
@@ -94,6 +348,7 @@ pub fn feeds_table() -> Vec<TableStatement> {
                 .auto_increment()
                 .primary_key(),
         )
+        .col(ColumnDef::new(Feeds::User).integer())
         .col(ColumnDef::new(Feeds::Title).text().not_null())
         .col(ColumnDef::new(Feeds::Link).text().not_null())
         .col(
@@ -110,13 +365,40 @@ pub fn feeds_table() -> Vec<TableStatement> {
                 .not_null()
                 .default(true),
         )
+        .col(ColumnDef::new(Feeds::SiteUrl).text())
+        .col(ColumnDef::new(Feeds::Description).text())
+        .col(ColumnDef::new(Feeds::Image).text())
+        .col(ColumnDef::new(Feeds::Language).text())
+        .col(ColumnDef::new(Feeds::Author).text())
+        .col(
+            ColumnDef::new(Feeds::FetchFullContent)
+                .boolean()
+                .not_null()
+                .default(false),
+        )
+        .col(
+            ColumnDef::new(Feeds::Kind)
+                .text()
+                .not_null()
+                .default("syndication"),
+        )
+        .col(ColumnDef::new(Feeds::SeenMark).text())
         .index(
             Index::create()
                 .unique()
-                .name("uk_feeds_title_link")
+                .name("uk_feeds_user_title_link")
+                .col(Feeds::User)
                 .col(Feeds::Title)
                 .col(Feeds::Link),
         )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_feeds_users")
+                .from(Feeds::Table, Feeds::User)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
         .to_owned();
 
     let alter_stmt = Table::alter()
@@ -129,10 +411,66 @@ pub fn feeds_table() -> Vec<TableStatement> {
         )
         .to_owned();
 
-    vec![
+    let add_user_stmt = Table::alter()
+        .table(Feeds::Table)
+        .add_column_if_not_exists(ColumnDef::new(Feeds::User).integer())
+        .to_owned();
+
+    let add_metadata_stmts = [
+        Feeds::SiteUrl,
+        Feeds::Description,
+        Feeds::Image,
+        Feeds::Language,
+        Feeds::Author,
+    ]
+    .into_iter()
+    .map(|column| {
+        TableStatement::Alter(
+            Table::alter()
+                .table(Feeds::Table)
+                .add_column_if_not_exists(ColumnDef::new(column).text())
+                .to_owned(),
+        )
+    });
+
+    let add_fetch_full_content_stmt = Table::alter()
+        .table(Feeds::Table)
+        .add_column_if_not_exists(
+            ColumnDef::new(Feeds::FetchFullContent)
+                .boolean()
+                .not_null()
+                .default(false),
+        )
+        .to_owned();
+
+    let add_kind_stmt = Table::alter()
+        .table(Feeds::Table)
+        .add_column_if_not_exists(
+            ColumnDef::new(Feeds::Kind)
+                .text()
+                .not_null()
+                .default("syndication"),
+        )
+        .to_owned();
+
+    let add_seen_mark_stmt = Table::alter()
+        .table(Feeds::Table)
+        .add_column_if_not_exists(ColumnDef::new(Feeds::SeenMark).text())
+        .to_owned();
+
+    [
         TableStatement::Create(create_stmt),
         TableStatement::Alter(alter_stmt),
+        TableStatement::Alter(add_user_stmt),
     ]
+    .into_iter()
+    .chain(add_metadata_stmts)
+    .chain([
+        TableStatement::Alter(add_fetch_full_content_stmt),
+        TableStatement::Alter(add_kind_stmt),
+        TableStatement::Alter(add_seen_mark_stmt),
+    ])
+    .collect()
 }
 
 pub fn items_table() -> Vec<TableStatement> {
@@ -146,15 +484,12 @@ pub fn items_table() -> Vec<TableStatement> {
                 .auto_increment()
                 .primary_key(),
         )
-        .col(
-            ColumnDef::new(Items::Fingerprint)
-                .text()
-                .not_null()
-                .unique_key(),
-        )
+        .col(ColumnDef::new(Items::User).integer())
+        .col(ColumnDef::new(Items::Fingerprint).text().not_null())
         .col(ColumnDef::new(Items::Author).text())
         .col(ColumnDef::new(Items::Title).text().not_null())
         .col(ColumnDef::new(Items::Description).text().not_null())
+        .col(ColumnDef::new(Items::Content).text())
         .col(ColumnDef::new(Items::Link).text().not_null())
         .col(
             ColumnDef::new(Items::Status)
@@ -172,6 +507,16 @@ pub fn items_table() -> Vec<TableStatement> {
         )
         .col(ColumnDef::new(Items::PublishedAt).date_time().not_null())
         .col(ColumnDef::new(Items::Feed).integer().not_null())
+        .col(ColumnDef::new(Items::ReceivedAt).date_time())
+        .col(ColumnDef::new(Items::ReadAt).date_time())
+        .col(ColumnDef::new(Items::SavedAt).date_time())
+        .index(
+            Index::create()
+                .unique()
+                .name("uk_items_user_fingerprint")
+                .col(Items::User)
+                .col(Items::Fingerprint),
+        )
         .foreign_key(
             ForeignKey::create()
                 .name("fk_items_feeds")
@@ -182,5 +527,425 @@ pub fn items_table() -> Vec<TableStatement> {
         )
         .to_owned();
 
+    let alter_stmt = Table::alter()
+        .table(Items::Table)
+        .add_column_if_not_exists(ColumnDef::new(Items::User).integer())
+        .to_owned();
+
+    let add_content_stmt = Table::alter()
+        .table(Items::Table)
+        .add_column_if_not_exists(ColumnDef::new(Items::Content).text())
+        .to_owned();
+
+    let add_timestamp_stmts = [Items::ReceivedAt, Items::ReadAt, Items::SavedAt]
+        .into_iter()
+        .map(|column| {
+            TableStatement::Alter(
+                Table::alter()
+                    .table(Items::Table)
+                    .add_column_if_not_exists(ColumnDef::new(column).date_time())
+                    .to_owned(),
+            )
+        });
+
+    [
+        TableStatement::Create(create_stmt),
+        TableStatement::Alter(alter_stmt),
+        TableStatement::Alter(add_content_stmt),
+    ]
+    .into_iter()
+    .chain(add_timestamp_stmts)
+    .collect()
+}
+
+/// Items received before `received_at` existed are taken as received when published.
+pub fn items_backfill() -> Vec<UpdateStatement> {
+    vec![Query::update()
+        .table(Items::Table)
+        .value(Items::ReceivedAt, Expr::col(Items::PublishedAt))
+        .and_where(Expr::col(Items::ReceivedAt).is_null())
+        .to_owned()]
+}
+
+/// Indexes the times items are sorted by, after the user whose items are read.
+pub fn items_indexes() -> Vec<IndexCreateStatement> {
+    [
+        ("idx_items_user_received_at", Items::ReceivedAt),
+        ("idx_items_user_read_at", Items::ReadAt),
+        ("idx_items_user_saved_at", Items::SavedAt),
+    ]
+    .into_iter()
+    .map(|(name, column)| {
+        Index::create()
+            .if_not_exists()
+            .name(name)
+            .table(Items::Table)
+            .col(Items::User)
+            .col(column)
+            .to_owned()
+    })
+    .collect()
+}
+
+pub fn read_history_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(ReadHistory::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(ReadHistory::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(ReadHistory::User).integer().not_null())
+        .col(ColumnDef::new(ReadHistory::Item).integer().not_null())
+        .col(ColumnDef::new(ReadHistory::Batch).text().not_null())
+        .col(
+            ColumnDef::new(ReadHistory::Status)
+                .text()
+                .check(Expr::col(ReadHistory::Status).is_in(["unread", "read"]))
+                .not_null(),
+        )
+        .col(ColumnDef::new(ReadHistory::PreviousReadAt).date_time())
+        .col(
+            ColumnDef::new(ReadHistory::ChangedAt)
+                .date_time()
+                .not_null(),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_read_history_items")
+                .from(ReadHistory::Table, ReadHistory::Item)
+                .to(Items::Table, Items::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
+/// Lets batches be undone and pruned without scanning the whole history.
+pub fn read_history_indexes() -> Vec<IndexCreateStatement> {
+    vec![
+        Index::create()
+            .if_not_exists()
+            .name("idx_read_history_user_batch")
+            .table(ReadHistory::Table)
+            .col(ReadHistory::User)
+            .col(ReadHistory::Batch)
+            .to_owned(),
+        Index::create()
+            .if_not_exists()
+            .name("idx_read_history_changed_at")
+            .table(ReadHistory::Table)
+            .col(ReadHistory::ChangedAt)
+            .to_owned(),
+    ]
+}
+
+pub fn retentions_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(Retentions::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Retentions::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(Retentions::Feed)
+                .integer()
+                .not_null()
+                .unique_key(),
+        )
+        .col(ColumnDef::new(Retentions::MaxAge).big_integer())
+        .col(ColumnDef::new(Retentions::MaxCount).big_integer())
+        .col(
+            ColumnDef::new(Retentions::DeleteUnread)
+                .boolean()
+                .not_null()
+                .default(false),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_retentions_feeds")
+                .from(Retentions::Table, Retentions::Feed)
+                .to(Feeds::Table, Feeds::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
+pub fn purges_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(Purges::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Purges::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Purges::User).integer().not_null())
+        .col(ColumnDef::new(Purges::Feed).integer().not_null())
+        .col(ColumnDef::new(Purges::Fingerprint).text().not_null())
+        .col(ColumnDef::new(Purges::PurgedAt).date_time().not_null())
+        .index(
+            Index::create()
+                .unique()
+                .name("uk_purges_user_fingerprint")
+                .col(Purges::User)
+                .col(Purges::Fingerprint),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_purges_feeds")
+                .from(Purges::Table, Purges::Feed)
+                .to(Feeds::Table, Feeds::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
+pub fn rules_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(Rules::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Rules::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Rules::User).integer().not_null())
+        .col(ColumnDef::new(Rules::Feed).integer())
+        .col(
+            ColumnDef::new(Rules::Field)
+                .text()
+                .check(Expr::col(Rules::Field).is_in(["title", "author", "link", "description"]))
+                .not_null(),
+        )
+        .col(ColumnDef::new(Rules::Pattern).text().not_null())
+        .col(
+            ColumnDef::new(Rules::IsRegex)
+                .boolean()
+                .not_null()
+                .default(false),
+        )
+        .col(
+            ColumnDef::new(Rules::Action)
+                .text()
+                .check(Expr::col(Rules::Action).is_in(["skip", "mark_read", "save", "tag"]))
+                .not_null(),
+        )
+        .col(ColumnDef::new(Rules::Tag).text())
+        .col(
+            ColumnDef::new(Rules::Matches)
+                .big_integer()
+                .not_null()
+                .default(0),
+        )
+        .col(ColumnDef::new(Rules::CreatedAt).date_time().not_null())
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_rules_users")
+                .from(Rules::Table, Rules::User)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_rules_feeds")
+                .from(Rules::Table, Rules::Feed)
+                .to(Feeds::Table, Feeds::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
+/// Items each rule skipped, so that items offered again on every poll are counted once.
+pub fn rule_skips_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(RuleSkips::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(RuleSkips::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(RuleSkips::Rule).integer().not_null())
+        .col(ColumnDef::new(RuleSkips::Feed).integer().not_null())
+        .col(ColumnDef::new(RuleSkips::Fingerprint).text().not_null())
+        .index(
+            Index::create()
+                .unique()
+                .name("uk_rule_skips_rule_feed_fingerprint")
+                .col(RuleSkips::Rule)
+                .col(RuleSkips::Feed)
+                .col(RuleSkips::Fingerprint),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_rule_skips_rules")
+                .from(RuleSkips::Table, RuleSkips::Rule)
+                .to(Rules::Table, Rules::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_rule_skips_feeds")
+                .from(RuleSkips::Table, RuleSkips::Feed)
+                .to(Feeds::Table, Feeds::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
+pub fn tags_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(Tags::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Tags::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Tags::Item).integer().not_null())
+        .col(ColumnDef::new(Tags::Name).text().not_null())
+        .index(
+            Index::create()
+                .unique()
+                .name("uk_tags_item_name")
+                .col(Tags::Item)
+                .col(Tags::Name),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_tags_items")
+                .from(Tags::Table, Tags::Item)
+                .to(Items::Table, Items::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
+pub fn icons_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(Icons::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(Icons::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Icons::Hash).text().not_null().unique_key())
+        .col(ColumnDef::new(Icons::Mime).text().not_null())
+        .col(ColumnDef::new(Icons::Data).blob().not_null())
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
+pub fn feed_selectors_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(FeedSelectors::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(FeedSelectors::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(FeedSelectors::Feed)
+                .integer()
+                .not_null()
+                .unique_key(),
+        )
+        .col(ColumnDef::new(FeedSelectors::Container).text().not_null())
+        .col(ColumnDef::new(FeedSelectors::Title).text().not_null())
+        .col(ColumnDef::new(FeedSelectors::Link).text().not_null())
+        .col(ColumnDef::new(FeedSelectors::Date).text())
+        .col(ColumnDef::new(FeedSelectors::Content).text())
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_feed_selectors_feeds")
+                .from(FeedSelectors::Table, FeedSelectors::Feed)
+                .to(Feeds::Table, Feeds::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
+    vec![TableStatement::Create(create_stmt)]
+}
+
+pub fn feed_icons_table() -> Vec<TableStatement> {
+    let create_stmt = Table::create()
+        .table(FeedIcons::Table)
+        .if_not_exists()
+        .col(
+            ColumnDef::new(FeedIcons::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(
+            ColumnDef::new(FeedIcons::Feed)
+                .integer()
+                .not_null()
+                .unique_key(),
+        )
+        .col(ColumnDef::new(FeedIcons::Icon).integer())
+        .col(ColumnDef::new(FeedIcons::CheckedAt).date_time().not_null())
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_feed_icons_feeds")
+                .from(FeedIcons::Table, FeedIcons::Feed)
+                .to(Feeds::Table, Feeds::Id)
+                .on_delete(ForeignKeyAction::Cascade)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .foreign_key(
+            ForeignKey::create()
+                .name("fk_feed_icons_icons")
+                .from(FeedIcons::Table, FeedIcons::Icon)
+                .to(Icons::Table, Icons::Id)
+                .on_delete(ForeignKeyAction::SetNull)
+                .on_update(ForeignKeyAction::Cascade),
+        )
+        .to_owned();
+
     vec![TableStatement::Create(create_stmt)]
 }
@@ -1,46 +1,83 @@
-use chrono::Utc;
-use sea_query::{Expr, Query, SqliteQueryBuilder};
-use sea_query_rusqlite::RusqliteBinder;
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_query::{Expr, OnConflict, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::{RusqliteBinder, RusqliteValues};
 
 use crate::{
     error::Result,
-    model::feed::{Feed, FeedToCreate, FeedToUpdate},
+    model::feed::{
+        Feed, FeedKind, FeedMetadata, FeedStatus, FeedToCreate, FeedToUpdate, ScrapeSelectors,
+    },
 };
 
-use super::database::{DbConnection, Feeds};
+use super::database::{DbConnection, FeedSelectors, Feeds};
 
-pub fn create(conn: &DbConnection, arg: &FeedToCreate) -> Result<usize> {
+pub fn create(
+    conn: &DbConnection,
+    user: i32,
+    arg: &FeedToCreate,
+    kind: &FeedKind,
+    metadata: &FeedMetadata,
+) -> Result<usize> {
     let (sql, values) = Query::insert()
         .into_table(Feeds::Table)
         .columns([
+            Feeds::User,
             Feeds::Title,
             Feeds::Link,
+            Feeds::Kind,
             Feeds::CheckedAt,
             Feeds::FetchOldItems,
+            Feeds::SiteUrl,
+            Feeds::Description,
+            Feeds::Image,
+            Feeds::Language,
+            Feeds::Author,
         ])
         .values_panic([
+            user.into(),
             (*arg.title).into(),
             (*arg.link).into(),
+            kind.to_string().into(),
             Utc::now().into(),
             arg.fetch_old_items.into(),
+            metadata.site_url.clone().into(),
+            metadata.description.clone().into(),
+            metadata.image.clone().into(),
+            metadata.language.clone().into(),
+            metadata.author.clone().into(),
         ])
         .build_rusqlite(SqliteQueryBuilder);
 
     let db = conn.lock().unwrap();
-    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+    let count = db.execute(sql.as_str(), &*values.as_params())?;
+    if let Some(selectors) = &arg.selectors {
+        let id = db.last_insert_rowid() as i32;
+        let (sql, values) = upsert_selectors_stmt(id, selectors);
+        db.execute(sql.as_str(), &*values.as_params())?;
+    }
+
+    Ok(count)
 }
 
-pub fn read_all(conn: &DbConnection) -> Result<Vec<Feed>> {
+pub fn read_all(conn: &DbConnection, user: i32) -> Result<Vec<Feed>> {
     let (sql, values) = Query::select()
         .columns([
             Feeds::Id,
             Feeds::Title,
             Feeds::Link,
+            Feeds::Kind,
             Feeds::Status,
             Feeds::CheckedAt,
             Feeds::FetchOldItems,
+            Feeds::SiteUrl,
+            Feeds::Description,
+            Feeds::Image,
+            Feeds::Language,
+            Feeds::Author,
+            Feeds::FetchFullContent,
         ])
         .from(Feeds::Table)
+        .and_where(Expr::col(Feeds::User).eq(user))
         .build_rusqlite(SqliteQueryBuilder);
 
     let db = conn.lock().unwrap();
@@ -50,17 +87,69 @@ pub fn read_all(conn: &DbConnection) -> Result<Vec<Feed>> {
     Ok(rows.map(std::result::Result::unwrap).collect::<Vec<Feed>>())
 }
 
-pub fn read(conn: &DbConnection, id: i32) -> Result<Option<Feed>> {
+/// Reads subscribed feeds of every user, for the worker to fetch.
+pub fn read_all_subscribed(conn: &DbConnection) -> Result<Vec<Feed>> {
     let (sql, values) = Query::select()
         .columns([
             Feeds::Id,
             Feeds::Title,
             Feeds::Link,
+            Feeds::Kind,
             Feeds::Status,
             Feeds::CheckedAt,
             Feeds::FetchOldItems,
+            Feeds::SiteUrl,
+            Feeds::Description,
+            Feeds::Image,
+            Feeds::Language,
+            Feeds::Author,
+            Feeds::FetchFullContent,
         ])
         .from(Feeds::Table)
+        .and_where(Expr::col(Feeds::User).is_not_null())
+        .and_where(Expr::col(Feeds::Status).eq(FeedStatus::Subscribed.to_string()))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Feed::from(x)))?;
+
+    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<Feed>>())
+}
+
+/// Reads ids of the feeds of every user.
+pub fn read_all_ids(conn: &DbConnection) -> Result<Vec<i32>> {
+    let (sql, values) = Query::select()
+        .column(Feeds::Id)
+        .from(Feeds::Table)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| x.get::<_, i32>(0))?;
+
+    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<i32>>())
+}
+
+pub fn read(conn: &DbConnection, user: i32, id: i32) -> Result<Option<Feed>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Feeds::Id,
+            Feeds::Title,
+            Feeds::Link,
+            Feeds::Kind,
+            Feeds::Status,
+            Feeds::CheckedAt,
+            Feeds::FetchOldItems,
+            Feeds::SiteUrl,
+            Feeds::Description,
+            Feeds::Image,
+            Feeds::Language,
+            Feeds::Author,
+            Feeds::FetchFullContent,
+        ])
+        .from(Feeds::Table)
+        .and_where(Expr::col(Feeds::User).eq(user))
         .and_where(Expr::col(Feeds::Id).eq(id))
         .limit(1)
         .build_rusqlite(SqliteQueryBuilder);
@@ -72,7 +161,7 @@ pub fn read(conn: &DbConnection, id: i32) -> Result<Option<Feed>> {
     Ok(rows.next()?.map(Feed::from))
 }
 
-pub fn update(conn: &DbConnection, arg: &FeedToUpdate) -> Result<usize> {
+pub fn update(conn: &DbConnection, user: i32, arg: &FeedToUpdate) -> Result<usize> {
     let mut vals = vec![];
 
     if let Some(title) = &arg.title {
@@ -95,9 +184,18 @@ pub fn update(conn: &DbConnection, arg: &FeedToUpdate) -> Result<usize> {
         vals.push((Feeds::FetchOldItems, fetch_old_items.into()));
     }
 
+    if let Some(fetch_full_content) = arg.fetch_full_content {
+        vals.push((Feeds::FetchFullContent, fetch_full_content.into()));
+    }
+
+    if vals.is_empty() {
+        return Ok(0);
+    }
+
     let (sql, values) = Query::update()
         .table(Feeds::Table)
         .values(vals)
+        .and_where(Expr::col(Feeds::User).eq(user))
         .and_where(Expr::col(Feeds::Id).eq(arg.id))
         .build_rusqlite(SqliteQueryBuilder);
 
@@ -105,9 +203,136 @@ pub fn update(conn: &DbConnection, arg: &FeedToUpdate) -> Result<usize> {
     Ok(db.execute(sql.as_str(), &*values.as_params())?)
 }
 
-pub fn delete(conn: &DbConnection, id: i32) -> Result<usize> {
+pub fn update_checked_at(
+    conn: &DbConnection,
+    ids: &[i32],
+    checked_at: DateTime<FixedOffset>,
+) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Feeds::Table)
+        .values([(Feeds::CheckedAt, checked_at.into())])
+        .and_where(Expr::col(Feeds::Id).is_in(ids.to_vec()))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Reads the fingerprint of the top item of a scraped feed at its last poll.
+pub fn read_seen_mark(conn: &DbConnection, feed: i32) -> Result<Option<String>> {
+    let (sql, values) = Query::select()
+        .column(Feeds::SeenMark)
+        .from(Feeds::Table)
+        .and_where(Expr::col(Feeds::Id).eq(feed))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows
+        .next()?
+        .and_then(|row| row.get_unwrap::<_, Option<String>>(0)))
+}
+
+pub fn update_seen_mark(conn: &DbConnection, feed: i32, mark: &str) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Feeds::Table)
+        .values([(Feeds::SeenMark, mark.into())])
+        .and_where(Expr::col(Feeds::Id).eq(feed))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn update_metadata(conn: &DbConnection, ids: &[i32], metadata: &FeedMetadata) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Feeds::Table)
+        .values([
+            (Feeds::SiteUrl, metadata.site_url.clone().into()),
+            (Feeds::Description, metadata.description.clone().into()),
+            (Feeds::Image, metadata.image.clone().into()),
+            (Feeds::Language, metadata.language.clone().into()),
+            (Feeds::Author, metadata.author.clone().into()),
+        ])
+        .and_where(Expr::col(Feeds::Id).is_in(ids.to_vec()))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Reads the selectors of a scraped feed.
+pub fn read_selectors(conn: &DbConnection, feed: i32) -> Result<Option<ScrapeSelectors>> {
+    let (sql, values) = Query::select()
+        .columns([
+            FeedSelectors::Container,
+            FeedSelectors::Title,
+            FeedSelectors::Link,
+            FeedSelectors::Date,
+            FeedSelectors::Content,
+        ])
+        .from(FeedSelectors::Table)
+        .and_where(Expr::col(FeedSelectors::Feed).eq(feed))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(ScrapeSelectors::from))
+}
+
+pub fn upsert_selectors(
+    conn: &DbConnection,
+    feed: i32,
+    selectors: &ScrapeSelectors,
+) -> Result<usize> {
+    let (sql, values) = upsert_selectors_stmt(feed, selectors);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+fn upsert_selectors_stmt(feed: i32, selectors: &ScrapeSelectors) -> (String, RusqliteValues) {
+    Query::insert()
+        .into_table(FeedSelectors::Table)
+        .columns([
+            FeedSelectors::Feed,
+            FeedSelectors::Container,
+            FeedSelectors::Title,
+            FeedSelectors::Link,
+            FeedSelectors::Date,
+            FeedSelectors::Content,
+        ])
+        .values_panic([
+            feed.into(),
+            selectors.container.clone().into(),
+            selectors.title.clone().into(),
+            selectors.link.clone().into(),
+            selectors.date.clone().into(),
+            selectors.content.clone().into(),
+        ])
+        .on_conflict(
+            OnConflict::column(FeedSelectors::Feed)
+                .update_columns([
+                    FeedSelectors::Container,
+                    FeedSelectors::Title,
+                    FeedSelectors::Link,
+                    FeedSelectors::Date,
+                    FeedSelectors::Content,
+                ])
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder)
+}
+
+pub fn delete(conn: &DbConnection, user: i32, id: i32) -> Result<usize> {
     let (sql, values) = Query::delete()
         .from_table(Feeds::Table)
+        .and_where(Expr::col(Feeds::User).eq(user))
         .and_where(Expr::col(Feeds::Id).eq(id))
         .build_rusqlite(SqliteQueryBuilder);
 
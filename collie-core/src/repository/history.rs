@@ -0,0 +1,92 @@
+use chrono::{DateTime, FixedOffset};
+use sea_query::{Expr, Query, SimpleExpr, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+use std::str::FromStr;
+
+use crate::{error::Result, model::item::ItemStatus};
+
+use super::database::{has_table, DbConnection, Items, ReadHistory};
+
+/// Puts the items of a batch of `update_all` back in the state they were in before it,
+/// except those changed again since. Single items updated with `update` aren't recorded
+/// in any batch. The batch is forgotten, so it can only be undone once.
+pub fn undo(conn: &DbConnection, user: i32, batch: &str) -> Result<usize> {
+    let (sql, values) = Query::select()
+        .column(ReadHistory::Status)
+        .from(ReadHistory::Table)
+        .and_where(Expr::col(ReadHistory::User).eq(user))
+        .and_where(Expr::col(ReadHistory::Batch).eq(batch))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut db = conn.lock().unwrap();
+    let tx = db.transaction()?;
+    // Without the read history there is nothing to undo.
+    if !has_table(&tx, "read_history") {
+        return Ok(0);
+    }
+
+    let status = {
+        let mut stmt = tx.prepare(sql.as_str())?;
+        let mut rows = stmt.query(&*values.as_params())?;
+        match rows.next()? {
+            Some(row) => ItemStatus::from_str(&row.get_unwrap::<_, String>(0))?,
+            None => return Ok(0),
+        }
+    };
+    let previous = match status {
+        ItemStatus::Read => ItemStatus::Unread,
+        ItemStatus::Unread => ItemStatus::Read,
+    };
+
+    let history = Query::select()
+        .column(ReadHistory::Item)
+        .from(ReadHistory::Table)
+        .and_where(Expr::col(ReadHistory::User).eq(user))
+        .and_where(Expr::col(ReadHistory::Batch).eq(batch))
+        .to_owned();
+    let previous_read_at = Query::select()
+        .column((ReadHistory::Table, ReadHistory::PreviousReadAt))
+        .from(ReadHistory::Table)
+        .and_where(Expr::col((ReadHistory::Table, ReadHistory::Batch)).eq(batch))
+        .and_where(
+            Expr::col((ReadHistory::Table, ReadHistory::Item)).equals((Items::Table, Items::Id)),
+        )
+        .limit(1)
+        .to_owned();
+
+    let (sql, values) = Query::update()
+        .table(Items::Table)
+        .values([
+            (Items::Status, previous.to_string().into()),
+            (
+                Items::ReadAt,
+                SimpleExpr::SubQuery(None, Box::new(previous_read_at.into_sub_query_statement())),
+            ),
+        ])
+        .and_where(Expr::col(Items::User).eq(user))
+        .and_where(Expr::col(Items::Status).eq(status.to_string()))
+        .and_where(Expr::col(Items::Id).in_subquery(history))
+        .build_rusqlite(SqliteQueryBuilder);
+    let count = tx.execute(sql.as_str(), &*values.as_params())?;
+
+    let (sql, values) = Query::delete()
+        .from_table(ReadHistory::Table)
+        .and_where(Expr::col(ReadHistory::User).eq(user))
+        .and_where(Expr::col(ReadHistory::Batch).eq(batch))
+        .build_rusqlite(SqliteQueryBuilder);
+    tx.execute(sql.as_str(), &*values.as_params())?;
+    tx.commit()?;
+
+    Ok(count)
+}
+
+pub fn delete_before(conn: &DbConnection, before: DateTime<FixedOffset>) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(ReadHistory::Table)
+        .and_where(Expr::col(ReadHistory::ChangedAt).lt(before.to_utc()))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
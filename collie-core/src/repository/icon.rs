@@ -0,0 +1,136 @@
+use chrono::{DateTime, FixedOffset};
+use sea_query::{Cond, Expr, OnConflict, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+use sha1_smol::Sha1;
+
+use crate::{error::Result, model::feed::Feed};
+
+use super::database::{DbConnection, FeedIcons, Feeds, Icons};
+
+/// Stores an icon unless the same bytes are already stored, and returns its id either way.
+pub fn create(conn: &DbConnection, mime: &str, data: &[u8]) -> Result<i32> {
+    let hash = Sha1::from(data).hexdigest();
+
+    let (sql, values) = Query::insert()
+        .into_table(Icons::Table)
+        .columns([Icons::Hash, Icons::Mime, Icons::Data])
+        .values_panic([hash.clone().into(), mime.into(), data.to_vec().into()])
+        .on_conflict(OnConflict::column(Icons::Hash).do_nothing().to_owned())
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    db.execute(sql.as_str(), &*values.as_params())?;
+
+    let (sql, values) = Query::select()
+        .column(Icons::Id)
+        .from(Icons::Table)
+        .and_where(Expr::col(Icons::Hash).eq(hash))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    Ok(db.query_row(sql.as_str(), &*values.as_params(), |x| x.get(0))?)
+}
+
+/// Sets the icon of a feed. `None` records that the feed has no icon for now.
+pub fn attach(
+    conn: &DbConnection,
+    feed: i32,
+    icon: Option<i32>,
+    checked_at: DateTime<FixedOffset>,
+) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(FeedIcons::Table)
+        .columns([FeedIcons::Feed, FeedIcons::Icon, FeedIcons::CheckedAt])
+        .values_panic([feed.into(), icon.into(), checked_at.into()])
+        .on_conflict(
+            OnConflict::column(FeedIcons::Feed)
+                .update_columns([FeedIcons::Icon, FeedIcons::CheckedAt])
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read(conn: &DbConnection, user: i32, feed: i32) -> Result<Option<(String, Vec<u8>)>> {
+    let (sql, values) = Query::select()
+        .columns([(Icons::Table, Icons::Mime), (Icons::Table, Icons::Data)])
+        .from(FeedIcons::Table)
+        .inner_join(
+            Icons::Table,
+            Expr::col((FeedIcons::Table, FeedIcons::Icon)).equals((Icons::Table, Icons::Id)),
+        )
+        .inner_join(
+            Feeds::Table,
+            Expr::col((FeedIcons::Table, FeedIcons::Feed)).equals((Feeds::Table, Feeds::Id)),
+        )
+        .and_where(Expr::col((Feeds::Table, Feeds::User)).eq(user))
+        .and_where(Expr::col((Feeds::Table, Feeds::Id)).eq(feed))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows
+        .next()?
+        .map(|row| (row.get_unwrap("mime"), row.get_unwrap("data"))))
+}
+
+/// Reads feeds whose icon hasn't been looked for since `before`.
+pub fn read_all_stale(conn: &DbConnection, before: DateTime<FixedOffset>) -> Result<Vec<Feed>> {
+    let (sql, values) = Query::select()
+        .columns([
+            (Feeds::Table, Feeds::Id),
+            (Feeds::Table, Feeds::Title),
+            (Feeds::Table, Feeds::Link),
+            (Feeds::Table, Feeds::Kind),
+            (Feeds::Table, Feeds::Status),
+            (Feeds::Table, Feeds::CheckedAt),
+            (Feeds::Table, Feeds::FetchOldItems),
+            (Feeds::Table, Feeds::SiteUrl),
+            (Feeds::Table, Feeds::Description),
+            (Feeds::Table, Feeds::Image),
+            (Feeds::Table, Feeds::Language),
+            (Feeds::Table, Feeds::Author),
+            (Feeds::Table, Feeds::FetchFullContent),
+        ])
+        .from(Feeds::Table)
+        .left_join(
+            FeedIcons::Table,
+            Expr::col((Feeds::Table, Feeds::Id)).equals((FeedIcons::Table, FeedIcons::Feed)),
+        )
+        .and_where(Expr::col((Feeds::Table, Feeds::User)).is_not_null())
+        .cond_where(
+            Cond::any()
+                .add(Expr::col((FeedIcons::Table, FeedIcons::CheckedAt)).is_null())
+                .add(Expr::col((FeedIcons::Table, FeedIcons::CheckedAt)).lt(before)),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Feed::from(x)))?;
+
+    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<Feed>>())
+}
+
+/// Deletes icons no feed uses anymore.
+pub fn delete_unused(conn: &DbConnection) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Icons::Table)
+        .and_where(
+            Expr::col(Icons::Id).not_in_subquery(
+                Query::select()
+                    .column(FeedIcons::Icon)
+                    .from(FeedIcons::Table)
+                    .and_where(Expr::col(FeedIcons::Icon).is_not_null())
+                    .to_owned(),
+            ),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
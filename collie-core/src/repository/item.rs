@@ -1,37 +1,66 @@
-use sea_query::{Alias, Expr, Func, Order, Query, SqliteQueryBuilder, Values};
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_query::{
+    Alias, Cond, Expr, Func, Keyword, LikeExpr, Order, Query, SelectStatement, SimpleExpr,
+    SqliteQueryBuilder, Value,
+};
 use sea_query_rusqlite::RusqliteBinder;
 
 use crate::{
-    error::Result,
+    error::{Error, Result},
+    model::feed::FeedStatus,
     model::item::{
-        Item, ItemOrder, ItemReadOption, ItemStatus, ItemToCreate, ItemToUpdate, ItemToUpdateAll,
+        DailyItemCounts, FeedItemCounts, Item, ItemCursor, ItemOrder, ItemPage, ItemReadOption,
+        ItemStatus, ItemToCreate, ItemToUpdate, ItemToUpdateAll,
     },
 };
 
-use super::database::{DbConnection, Feeds, Items};
+use super::database::{has_table, DbConnection, Feeds, Items, ReadHistory};
 
 pub fn create(conn: &DbConnection, arg: &ItemToCreate) -> Result<usize> {
+    let now = Utc::now().fixed_offset();
     let (sql, values) = Query::insert()
         .into_table(Items::Table)
         .columns([
+            Items::User,
             Items::Fingerprint,
             Items::Author,
             Items::Title,
             Items::Description,
+            Items::Content,
             Items::Link,
             Items::Status,
+            Items::IsSaved,
             Items::PublishedAt,
             Items::Feed,
+            Items::ReceivedAt,
+            Items::ReadAt,
+            Items::SavedAt,
         ])
         .values_panic([
+            SimpleExpr::SubQuery(
+                None,
+                Box::new(
+                    Query::select()
+                        .column(Feeds::User)
+                        .from(Feeds::Table)
+                        .and_where(Expr::col(Feeds::Id).eq(arg.feed))
+                        .to_owned()
+                        .into_sub_query_statement(),
+                ),
+            ),
             arg.fingerprint().into(),
             arg.author.clone().into(),
             arg.title.clone().into(),
             arg.description.clone().into(),
+            arg.content.clone().into(),
             arg.link.clone().into(),
             arg.status.to_string().into(),
+            arg.is_saved.into(),
             arg.published_at.into(),
             arg.feed.into(),
+            now.into(),
+            matches!(arg.status, ItemStatus::Read).then_some(now).into(),
+            arg.is_saved.then_some(now).into(),
         ])
         .build_rusqlite(SqliteQueryBuilder);
 
@@ -39,7 +68,43 @@ pub fn create(conn: &DbConnection, arg: &ItemToCreate) -> Result<usize> {
     Ok(db.execute(sql.as_str(), &*values.as_params())?)
 }
 
-pub fn read_all(conn: &DbConnection, opt: &ItemReadOption) -> Result<Vec<Item>> {
+pub fn read_all(conn: &DbConnection, user: i32, opt: &ItemReadOption) -> Result<Vec<Item>> {
+    read(conn, user, opt, opt.limit)
+}
+
+/// Reads a page of items, with cursors to the pages before and after it.
+pub fn read_page(conn: &DbConnection, user: i32, opt: &ItemReadOption) -> Result<ItemPage> {
+    // One more item than asked tells whether there is another page past this one.
+    let mut items = read(conn, user, opt, opt.limit.map(|x| x + 1))?;
+    let has_more = opt.limit.is_some_and(|x| items.len() as u64 > x);
+    if has_more {
+        if opt.before.is_some() {
+            items.remove(0);
+        } else {
+            items.pop();
+        }
+    }
+
+    let cursor = |item: Option<&Item>| item.map(|x| ItemCursor::from(x).to_string());
+    let (has_prev, has_next) = if opt.before.is_some() {
+        (has_more, true)
+    } else {
+        (opt.after.is_some(), has_more)
+    };
+
+    Ok(ItemPage {
+        prev: cursor(items.first().filter(|_| has_prev)),
+        next: cursor(items.last().filter(|_| has_next)),
+        items,
+    })
+}
+
+fn read(
+    conn: &DbConnection,
+    user: i32,
+    opt: &ItemReadOption,
+    limit: Option<u64>,
+) -> Result<Vec<Item>> {
     let mut query = Query::select()
         .columns([
             (Items::Table, Items::Id),
@@ -47,10 +112,14 @@ pub fn read_all(conn: &DbConnection, opt: &ItemReadOption) -> Result<Vec<Item>>
             (Items::Table, Items::Author),
             (Items::Table, Items::Title),
             (Items::Table, Items::Description),
+            (Items::Table, Items::Content),
             (Items::Table, Items::Link),
             (Items::Table, Items::Status),
             (Items::Table, Items::IsSaved),
             (Items::Table, Items::PublishedAt),
+            (Items::Table, Items::ReceivedAt),
+            (Items::Table, Items::ReadAt),
+            (Items::Table, Items::SavedAt),
         ])
         .expr_as(Expr::col((Feeds::Table, Feeds::Id)), Alias::new("feed_id"))
         .expr_as(
@@ -66,83 +135,221 @@ pub fn read_all(conn: &DbConnection, opt: &ItemReadOption) -> Result<Vec<Item>>
             Feeds::Table,
             Expr::col((Items::Table, Items::Feed)).equals((Feeds::Table, Feeds::Id)),
         )
+        .cond_where(filter(user, opt))
         .clone();
 
+    // Items before a cursor are read in reverse, nearest first, and put back in order.
+    let (cursor, is_backward) = match (&opt.before, &opt.after) {
+        (Some(_), Some(_)) => return Err(Error::BadArgument),
+        (Some(x), None) => (Some(x.parse::<ItemCursor>()?), true),
+        (None, Some(x)) => (Some(x.parse::<ItemCursor>()?), false),
+        (None, None) => (None, false),
+    };
+
+    let keys = sort_keys(opt.order_by.unwrap_or_default());
+    if let Some(cursor) = &cursor {
+        query.cond_where(seek(&keys, cursor, is_backward));
+    }
+    for key in &keys {
+        let order = match (&key.order, is_backward) {
+            (Order::Asc, true) => Order::Desc,
+            (Order::Desc, true) => Order::Asc,
+            (order, _) => order.clone(),
+        };
+        query.order_by_expr(key.expr.clone(), order);
+    }
+
+    if let Some(limit) = limit {
+        query.limit(limit);
+    }
+
+    let db = conn.lock().unwrap();
+    let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Item::from(x)))?;
+
+    let mut items = rows.map(std::result::Result::unwrap).collect::<Vec<Item>>();
+    if is_backward {
+        items.reverse();
+    }
+
+    Ok(items)
+}
+
+/// Builds the conditions of the items of a user matching an option, shared by every
+/// query reading or updating items so that they all agree on what matches. Feed columns
+/// are matched with sub-queries, which work without joining the feeds. Cursors are left
+/// to reads, since they page through matching items rather than filter them.
+fn filter(user: i32, opt: &ItemReadOption) -> Cond {
+    let mut cond = Cond::all().add(Expr::col((Items::Table, Items::User)).eq(user));
+
     if let Some(ids) = &opt.ids {
-        query.and_where(Expr::col(Items::Id).is_in(ids.clone()));
+        cond = cond.add(Expr::col((Items::Table, Items::Id)).is_in(ids.clone()));
     }
 
-    if let Some(feed) = &opt.feed {
-        query.and_where(Expr::col(Items::Feed).eq(*feed));
+    if let Some(feed) = opt.feed {
+        cond = cond.add(Expr::col((Items::Table, Items::Feed)).eq(feed));
+    }
+
+    if !opt.feeds.is_empty() {
+        cond = cond.add(Expr::col((Items::Table, Items::Feed)).is_in(opt.feeds.clone()));
+    }
+
+    if !opt.exclude_feeds.is_empty() {
+        cond =
+            cond.add(Expr::col((Items::Table, Items::Feed)).is_not_in(opt.exclude_feeds.clone()));
     }
 
     if let Some(status) = &opt.status {
-        query.and_where(Expr::col((Items::Table, Items::Status)).eq(status.to_string()));
-    }
-
-    if let Some(is_saved) = &opt.is_saved {
-        query.and_where(Expr::col(Items::IsSaved).eq(*is_saved));
-    }
-
-    if let Some(order_by) = &opt.order_by {
-        match order_by {
-            ItemOrder::ReceivedDateDesc => {
-                query
-                    .order_by((Items::Table, Items::Id), Order::Desc)
-                    .order_by(Items::PublishedAt, Order::Desc);
-            }
-            ItemOrder::PublishedDateDesc => {
-                query.order_by(Items::PublishedAt, Order::Desc);
-            }
-            ItemOrder::UnreadFirst => {
-                query
-                    .order_by(
-                        (Items::Table, Items::Status),
-                        Order::Field(Values(vec![ItemStatus::Unread.to_string().into()])),
-                    )
-                    .order_by(Items::PublishedAt, Order::Desc);
-            }
-        }
+        cond = cond.add(Expr::col((Items::Table, Items::Status)).eq(status.to_string()));
     }
 
-    if let Some(limit) = &opt.limit {
-        query.limit(*limit);
+    if let Some(is_saved) = opt.is_saved {
+        cond = cond.add(Expr::col((Items::Table, Items::IsSaved)).eq(is_saved));
     }
 
-    if let Some(offset) = &opt.offset {
-        query.offset(if let Some(limit) = &opt.limit {
-            offset * limit
-        } else {
-            *offset
-        });
+    if let Some(published_after) = opt.published_after {
+        cond =
+            cond.add(Expr::col((Items::Table, Items::PublishedAt)).gte(published_after.to_utc()));
     }
 
-    let db = conn.lock().unwrap();
-    let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
-    let mut stmt = db.prepare(sql.as_str())?;
-    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Item::from(x)))?;
+    if let Some(published_before) = opt.published_before {
+        cond =
+            cond.add(Expr::col((Items::Table, Items::PublishedAt)).lt(published_before.to_utc()));
+    }
 
-    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<Item>>())
+    if let Some(author) = &opt.author {
+        cond = cond.add(Expr::col((Items::Table, Items::Author)).like(contains(author)));
+    }
+
+    if let Some(feed_status) = &opt.feed_status {
+        cond = cond.add(
+            Expr::col((Items::Table, Items::Feed)).in_subquery(feeds_with_status(feed_status)),
+        );
+    }
+
+    cond
 }
 
-pub fn count_all(conn: &DbConnection, opt: &ItemReadOption) -> Result<i64> {
-    let mut query = Query::select()
-        .from(Items::Table)
-        .expr(Func::count(Expr::col(Items::Id)))
-        .clone();
+/// Matches the text anywhere, with the wildcards of `LIKE` taken literally.
+fn contains(text: &str) -> LikeExpr {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    LikeExpr::new(format!("%{escaped}%")).escape('\\')
+}
 
-    if let Some(feed) = &opt.feed {
-        query.and_where(Expr::col(Items::Feed).eq(*feed));
-    }
+fn feeds_with_status(status: &FeedStatus) -> SelectStatement {
+    Query::select()
+        .column(Feeds::Id)
+        .from(Feeds::Table)
+        .and_where(Expr::col(Feeds::Status).eq(status.to_string()))
+        .to_owned()
+}
 
-    if let Some(status) = &opt.status {
-        query.and_where(Expr::col((Items::Table, Items::Status)).eq(status.to_string()));
+/// A column items are sorted by, and its value in the item a cursor points to.
+struct SortKey {
+    expr: SimpleExpr,
+    order: Order,
+    value: fn(&ItemCursor) -> Value,
+    /// What a missing value is compared as when seeking, for columns that may be null.
+    missing: Option<Value>,
+}
+
+/// Every order ends with the id, so that items sort the same way on every read.
+fn sort_keys(order: ItemOrder) -> Vec<SortKey> {
+    let id = SortKey {
+        expr: Expr::col((Items::Table, Items::Id)).into(),
+        order: Order::Desc,
+        value: |x| x.id.into(),
+        missing: None,
+    };
+    let published_at = SortKey {
+        expr: Expr::col((Items::Table, Items::PublishedAt)).into(),
+        order: Order::Desc,
+        value: |x| x.published_at.into(),
+        missing: None,
+    };
+    // SQLite sorts missing times last, as if they were empty text, which comes before
+    // any stored time. Sorting on the bare column lets the index on it be used.
+    let latest_first = |column: Items, value: fn(&ItemCursor) -> Value| SortKey {
+        expr: Expr::col((Items::Table, column)).into(),
+        order: Order::Desc,
+        value,
+        missing: Some("".into()),
+    };
+
+    match order {
+        ItemOrder::ReceivedDateDesc => vec![
+            latest_first(Items::ReceivedAt, |x| {
+                x.received_at.map_or("".into(), Into::into)
+            }),
+            id,
+        ],
+        ItemOrder::ReadDateDesc => vec![
+            latest_first(Items::ReadAt, |x| x.read_at.map_or("".into(), Into::into)),
+            id,
+        ],
+        ItemOrder::SavedDateDesc => vec![
+            latest_first(Items::SavedAt, |x| x.saved_at.map_or("".into(), Into::into)),
+            id,
+        ],
+        ItemOrder::PublishedDateDesc => vec![published_at, id],
+        ItemOrder::UnreadFirst => vec![
+            SortKey {
+                expr: Expr::case(
+                    Expr::col((Items::Table, Items::Status)).eq(ItemStatus::Unread.to_string()),
+                    0,
+                )
+                .finally(1)
+                .into(),
+                order: Order::Asc,
+                value: |x| match x.status {
+                    ItemStatus::Unread => 0.into(),
+                    ItemStatus::Read => 1.into(),
+                },
+                missing: None,
+            },
+            published_at,
+            id,
+        ],
     }
+}
 
-    if let Some(is_saved) = &opt.is_saved {
-        query.and_where(Expr::col(Items::IsSaved).eq(*is_saved));
+/// Matches the items sorting after the cursor, or before it when going backward.
+fn seek(keys: &[SortKey], cursor: &ItemCursor, is_backward: bool) -> Cond {
+    let mut cond = Cond::any();
+    if let Some((key, rest)) = keys.split_first() {
+        let value = (key.value)(cursor);
+        let expr = match &key.missing {
+            Some(missing) => Expr::expr(Func::if_null(key.expr.clone(), missing.clone())),
+            None => Expr::expr(key.expr.clone()),
+        };
+        cond = match (&key.order, is_backward) {
+            (Order::Desc, false) | (Order::Asc, true) => cond.add(expr.clone().lt(value.clone())),
+            _ => cond.add(expr.clone().gt(value.clone())),
+        };
+
+        if !rest.is_empty() {
+            cond = cond.add(
+                Cond::all()
+                    .add(expr.eq(value))
+                    .add(seek(rest, cursor, is_backward)),
+            );
+        }
     }
 
+    cond
+}
+
+pub fn count_all(conn: &DbConnection, user: i32, opt: &ItemReadOption) -> Result<i64> {
+    let query = Query::select()
+        .from(Items::Table)
+        .expr(Func::count(Expr::col((Items::Table, Items::Id))))
+        .cond_where(filter(user, opt))
+        .to_owned();
+
     let db = conn.lock().unwrap();
     let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
     let mut stmt = db.prepare(sql.as_str())?;
@@ -155,20 +362,114 @@ pub fn count_all(conn: &DbConnection, opt: &ItemReadOption) -> Result<i64> {
     })
 }
 
-pub fn update(conn: &DbConnection, arg: &ItemToUpdate) -> Result<usize> {
+/// Counts the items of every feed of a user in one query, feeds without items included.
+pub fn counts_by_feed(conn: &DbConnection, user: i32) -> Result<Vec<FeedItemCounts>> {
+    let (sql, values) = select_counts()
+        .expr_as(Expr::col((Feeds::Table, Feeds::Id)), Alias::new("feed"))
+        .from(Feeds::Table)
+        .left_join(
+            Items::Table,
+            Expr::col((Items::Table, Items::Feed)).equals((Feeds::Table, Feeds::Id)),
+        )
+        .and_where(Expr::col((Feeds::Table, Feeds::User)).eq(user))
+        .group_by_col((Feeds::Table, Feeds::Id))
+        .order_by((Feeds::Table, Feeds::Id), Order::Asc)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(FeedItemCounts::from(x)))?;
+
+    Ok(rows
+        .map(std::result::Result::unwrap)
+        .collect::<Vec<FeedItemCounts>>())
+}
+
+/// Counts the items matching an option by the day they were published, skipping days
+/// without any.
+pub fn counts_by_day(
+    conn: &DbConnection,
+    user: i32,
+    opt: &ItemReadOption,
+) -> Result<Vec<DailyItemCounts>> {
+    let day = Func::cust(Alias::new("date")).arg(Expr::col((Items::Table, Items::PublishedAt)));
+    let (sql, values) = select_counts()
+        .expr_as(day.clone(), Alias::new("day"))
+        .from(Items::Table)
+        .cond_where(filter(user, opt))
+        .add_group_by([day.clone().into()])
+        .order_by_expr(day.into(), Order::Asc)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(DailyItemCounts::from(x)))?;
+
+    Ok(rows
+        .map(std::result::Result::unwrap)
+        .collect::<Vec<DailyItemCounts>>())
+}
+
+/// Selects the columns of `ItemCounts` over the items of each group.
+fn select_counts() -> SelectStatement {
+    let count_if = |cond: SimpleExpr| Func::sum(Expr::case(cond, 1).finally(0));
+
+    Query::select()
+        .expr_as(
+            count_if(Expr::col((Items::Table, Items::Status)).eq(ItemStatus::Unread.to_string())),
+            Alias::new("unread"),
+        )
+        .expr_as(
+            count_if(Expr::col((Items::Table, Items::IsSaved)).eq(true)),
+            Alias::new("saved"),
+        )
+        .expr_as(
+            Func::count(Expr::col((Items::Table, Items::Id))),
+            Alias::new("total"),
+        )
+        .to_owned()
+}
+
+pub fn latest_published_at(
+    conn: &DbConnection,
+    feed: i32,
+) -> Result<Option<DateTime<FixedOffset>>> {
+    let (sql, values) = Query::select()
+        .column(Items::PublishedAt)
+        .from(Items::Table)
+        .and_where(Expr::col(Items::Feed).eq(feed))
+        .order_by(Items::PublishedAt, Order::Desc)
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(|row| row.get_unwrap(0)))
+}
+
+pub fn update(conn: &DbConnection, user: i32, arg: &ItemToUpdate) -> Result<usize> {
+    let now = Utc::now().fixed_offset();
     let mut vals = vec![];
 
     if let Some(status) = &arg.status {
         vals.push((Items::Status, status.to_string().into()));
+        vals.push((
+            Items::ReadAt,
+            stamp(Items::ReadAt, *status == ItemStatus::Read, now),
+        ));
     }
 
     if let Some(is_saved) = &arg.is_saved {
         vals.push((Items::IsSaved, (*is_saved).into()));
+        vals.push((Items::SavedAt, stamp(Items::SavedAt, *is_saved, now)));
     }
 
     let (sql, values) = Query::update()
         .table(Items::Table)
         .values(vals)
+        .and_where(Expr::col(Items::User).eq(user))
         .and_where(Expr::col(Items::Id).eq(arg.id))
         .build_rusqlite(SqliteQueryBuilder);
 
@@ -176,38 +477,108 @@ pub fn update(conn: &DbConnection, arg: &ItemToUpdate) -> Result<usize> {
     Ok(db.execute(sql.as_str(), &*values.as_params())?)
 }
 
-pub fn update_all(conn: &DbConnection, arg: &ItemToUpdateAll) -> Result<usize> {
+/// Keeps the time an item was first marked, or clears it once the item is unmarked.
+fn stamp(column: Items, is_marked: bool, now: DateTime<FixedOffset>) -> SimpleExpr {
+    if is_marked {
+        Func::if_null(Expr::col(column), now).into()
+    } else {
+        SimpleExpr::Keyword(Keyword::Null)
+    }
+}
+
+/// Stores the full article of an item, found by the feed and fingerprint it was just
+/// inserted with.
+pub fn update_content(
+    conn: &DbConnection,
+    feed: i32,
+    fingerprint: &str,
+    content: &str,
+) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Items::Table)
+        .values([(Items::Content, content.into())])
+        .and_where(Expr::col(Items::Feed).eq(feed))
+        .and_where(Expr::col(Items::Fingerprint).eq(fingerprint))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Updates every item matching an option. Items changing between read and unread are
+/// recorded in the read history under `batch`, when it exists, so that the whole update
+/// can be undone.
+pub fn update_all(
+    conn: &DbConnection,
+    user: i32,
+    arg: &ItemToUpdateAll,
+    batch: &str,
+) -> Result<usize> {
+    let now = Utc::now().fixed_offset();
     let mut vals = vec![];
 
     if let Some(status) = &arg.status {
         vals.push((Items::Status, status.to_string().into()));
+        vals.push((
+            Items::ReadAt,
+            stamp(Items::ReadAt, *status == ItemStatus::Read, now),
+        ));
     }
 
     if let Some(is_saved) = &arg.is_saved {
         vals.push((Items::IsSaved, (*is_saved).into()));
+        vals.push((Items::SavedAt, stamp(Items::SavedAt, *is_saved, now)));
     }
 
-    let mut query = Query::update().table(Items::Table).values(vals).clone();
-
-    if let Some(opt) = &arg.opt {
-        if let Some(ids) = &opt.ids {
-            query.and_where(Expr::col(Items::Id).is_in(ids.clone()));
-        }
+    let cond = match &arg.opt {
+        Some(opt) => filter(user, opt),
+        None => filter(user, &ItemReadOption::default()),
+    };
+
+    let history = match &arg.status {
+        Some(status) => Some(
+            Query::insert()
+                .into_table(ReadHistory::Table)
+                .columns([
+                    ReadHistory::User,
+                    ReadHistory::Item,
+                    ReadHistory::Batch,
+                    ReadHistory::Status,
+                    ReadHistory::PreviousReadAt,
+                    ReadHistory::ChangedAt,
+                ])
+                .select_from(
+                    Query::select()
+                        .column((Items::Table, Items::User))
+                        .column((Items::Table, Items::Id))
+                        .expr(Expr::val(batch))
+                        .expr(Expr::val(status.to_string()))
+                        .column((Items::Table, Items::ReadAt))
+                        .expr(Expr::val(now))
+                        .from(Items::Table)
+                        .cond_where(cond.clone())
+                        .and_where(Expr::col((Items::Table, Items::Status)).ne(status.to_string()))
+                        .to_owned(),
+                )?
+                .build_rusqlite(SqliteQueryBuilder),
+        ),
+        None => None,
+    };
 
-        if let Some(feed) = &opt.feed {
-            query.and_where(Expr::col(Items::Feed).eq(*feed));
-        }
-
-        if let Some(status) = &opt.status {
-            query.and_where(Expr::col((Items::Table, Items::Status)).eq(status.to_string()));
-        }
+    let (sql, values) = Query::update()
+        .table(Items::Table)
+        .values(vals)
+        .cond_where(cond)
+        .build_rusqlite(SqliteQueryBuilder);
 
-        if let Some(is_saved) = &opt.is_saved {
-            query.and_where(Expr::col(Items::IsSaved).eq(*is_saved));
-        }
+    let mut db = conn.lock().unwrap();
+    let tx = db.transaction()?;
+    // Embedders that haven't migrated the read history keep updating without it.
+    if let Some((sql, values)) = history.filter(|_| has_table(&tx, "read_history")) {
+        tx.execute(sql.as_str(), &*values.as_params())?;
     }
+    let count = tx.execute(sql.as_str(), &*values.as_params())?;
+    tx.commit()?;
 
-    let db = conn.lock().unwrap();
-    let (sql, values) = query.build_rusqlite(SqliteQueryBuilder);
-    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+    Ok(count)
 }
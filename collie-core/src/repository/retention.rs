@@ -0,0 +1,274 @@
+use chrono::{DateTime, FixedOffset};
+use sea_query::{Cond, Expr, OnConflict, Order, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+use std::collections::HashSet;
+
+use crate::{
+    error::Result,
+    model::{
+        item::ItemStatus,
+        retention::{PurgeToCreate, Retention, RetentionPolicy},
+    },
+};
+
+use super::database::{DbConnection, Feeds, Items, Purges, Retentions};
+
+pub fn upsert(conn: &DbConnection, feed: i32, policy: &RetentionPolicy) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Retentions::Table)
+        .columns([
+            Retentions::Feed,
+            Retentions::MaxAge,
+            Retentions::MaxCount,
+            Retentions::DeleteUnread,
+        ])
+        .values_panic([
+            feed.into(),
+            policy.max_age.into(),
+            policy.max_count.into(),
+            policy.delete_unread.into(),
+        ])
+        .on_conflict(
+            OnConflict::column(Retentions::Feed)
+                .update_columns([
+                    Retentions::MaxAge,
+                    Retentions::MaxCount,
+                    Retentions::DeleteUnread,
+                ])
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read_all(conn: &DbConnection) -> Result<Vec<Retention>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Retentions::Feed,
+            Retentions::MaxAge,
+            Retentions::MaxCount,
+            Retentions::DeleteUnread,
+        ])
+        .from(Retentions::Table)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Retention::from(x)))?;
+
+    Ok(rows
+        .map(std::result::Result::unwrap)
+        .collect::<Vec<Retention>>())
+}
+
+pub fn read(conn: &DbConnection, feed: i32) -> Result<Option<Retention>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Retentions::Feed,
+            Retentions::MaxAge,
+            Retentions::MaxCount,
+            Retentions::DeleteUnread,
+        ])
+        .from(Retentions::Table)
+        .and_where(Expr::col(Retentions::Feed).eq(feed))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Retention::from))
+}
+
+pub fn delete(conn: &DbConnection, feed: i32) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Retentions::Table)
+        .and_where(Expr::col(Retentions::Feed).eq(feed))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Deletes the items of a feed that the policy no longer keeps, and remembers their
+/// fingerprints so that they aren't inserted again. Returns the number of deleted items.
+pub fn purge(
+    conn: &DbConnection,
+    feed: i32,
+    policy: &RetentionPolicy,
+    now: DateTime<FixedOffset>,
+) -> Result<usize> {
+    let max_age = policy.max_age();
+    if max_age.is_none() && policy.max_count.is_none() {
+        return Ok(0);
+    }
+
+    let mut deletable = Cond::all()
+        .add(Expr::col(Items::Feed).eq(feed))
+        .add(Expr::col(Items::IsSaved).eq(false));
+    if !policy.delete_unread {
+        deletable = deletable.add(Expr::col(Items::Status).eq(ItemStatus::Read.to_string()));
+    }
+
+    let mut expired = Cond::any();
+    if let Some(max_age) = max_age {
+        expired = expired.add(Expr::col(Items::PublishedAt).lt(now - max_age));
+    }
+    if let Some(max_count) = policy.max_count {
+        expired = expired.add(
+            Expr::col(Items::Id).not_in_subquery(
+                Query::select()
+                    .column(Items::Id)
+                    .from(Items::Table)
+                    .cond_where(deletable.clone())
+                    .order_by(Items::PublishedAt, Order::Desc)
+                    .order_by(Items::Id, Order::Desc)
+                    .limit(max_count)
+                    .to_owned(),
+            ),
+        );
+    }
+
+    let (select_sql, select_values) = Query::select()
+        .columns([Items::Id, Items::User, Items::Fingerprint])
+        .from(Items::Table)
+        .cond_where(deletable)
+        .cond_where(expired)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let mut db = conn.lock().unwrap();
+    let tx = db.transaction()?;
+
+    let targets = {
+        let mut stmt = tx.prepare(select_sql.as_str())?;
+        let rows = stmt.query_map(&*select_values.as_params(), |row| {
+            Ok((
+                row.get_unwrap::<_, i32>(0),
+                row.get_unwrap::<_, Option<i32>>(1),
+                row.get_unwrap::<_, String>(2),
+            ))
+        })?;
+        rows.map(std::result::Result::unwrap).collect::<Vec<_>>()
+    };
+
+    if targets.is_empty() {
+        return Ok(0);
+    }
+
+    let mut insert = Query::insert()
+        .into_table(Purges::Table)
+        .columns([
+            Purges::User,
+            Purges::Feed,
+            Purges::Fingerprint,
+            Purges::PurgedAt,
+        ])
+        .on_conflict(
+            OnConflict::columns([Purges::User, Purges::Fingerprint])
+                .update_columns([Purges::Feed, Purges::PurgedAt])
+                .to_owned(),
+        )
+        .to_owned();
+    let mut has_purges = false;
+    for (_, user, fingerprint) in &targets {
+        if let Some(user) = user {
+            insert.values_panic([(*user).into(), feed.into(), fingerprint.into(), now.into()]);
+            has_purges = true;
+        }
+    }
+    if has_purges {
+        let (sql, values) = insert.build_rusqlite(SqliteQueryBuilder);
+        tx.execute(sql.as_str(), &*values.as_params())?;
+    }
+
+    let (sql, values) = Query::delete()
+        .from_table(Items::Table)
+        .and_where(Expr::col(Items::Id).is_in(targets.iter().map(|(id, _, _)| *id)))
+        .build_rusqlite(SqliteQueryBuilder);
+    let deleted = tx.execute(sql.as_str(), &*values.as_params())?;
+
+    tx.commit()?;
+    Ok(deleted)
+}
+
+/// Tells which of the fingerprints were purged from a feed.
+pub fn read_purged_fingerprints(
+    conn: &DbConnection,
+    feed: i32,
+    fingerprints: &[String],
+) -> Result<HashSet<String>> {
+    if fingerprints.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let (sql, values) = Query::select()
+        .column(Purges::Fingerprint)
+        .from(Purges::Table)
+        .and_where(
+            Expr::col(Purges::User).in_subquery(
+                Query::select()
+                    .column(Feeds::User)
+                    .from(Feeds::Table)
+                    .and_where(Expr::col(Feeds::Id).eq(feed))
+                    .to_owned(),
+            ),
+        )
+        .and_where(Expr::col(Purges::Fingerprint).is_in(fingerprints.iter().map(String::as_str)))
+        .and_where(Expr::col(Purges::Feed).eq(feed))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |row| row.get::<_, String>(0))?;
+
+    Ok(rows.map(std::result::Result::unwrap).collect())
+}
+
+/// Remembers an item of a feed as purged without it ever having been inserted.
+pub fn create_purge(
+    conn: &DbConnection,
+    arg: &PurgeToCreate,
+    now: DateTime<FixedOffset>,
+) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Purges::Table)
+        .columns([
+            Purges::User,
+            Purges::Feed,
+            Purges::Fingerprint,
+            Purges::PurgedAt,
+        ])
+        .select_from(
+            Query::select()
+                .column(Feeds::User)
+                .column(Feeds::Id)
+                .expr(Expr::val(arg.fingerprint.clone()))
+                .expr(Expr::val(now))
+                .from(Feeds::Table)
+                .and_where(Expr::col(Feeds::Id).eq(arg.feed))
+                .and_where(Expr::col(Feeds::User).is_not_null())
+                .to_owned(),
+        )?
+        .on_conflict(
+            OnConflict::columns([Purges::User, Purges::Fingerprint])
+                .update_columns([Purges::Feed, Purges::PurgedAt])
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn delete_purges_before(conn: &DbConnection, before: DateTime<FixedOffset>) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Purges::Table)
+        .and_where(Expr::col(Purges::PurgedAt).lt(before.to_utc()))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
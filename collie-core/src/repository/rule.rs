@@ -0,0 +1,202 @@
+use chrono::Utc;
+use sea_query::{Cond, Expr, OnConflict, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{
+    error::Result,
+    model::rule::{Rule, RuleToCreate, RuleToUpdate},
+};
+
+use super::database::{DbConnection, Feeds, RuleSkips, Rules};
+
+pub fn create(conn: &DbConnection, user: i32, arg: &RuleToCreate) -> Result<i32> {
+    let (sql, values) = Query::insert()
+        .into_table(Rules::Table)
+        .columns([
+            Rules::User,
+            Rules::Feed,
+            Rules::Field,
+            Rules::Pattern,
+            Rules::IsRegex,
+            Rules::Action,
+            Rules::Tag,
+            Rules::CreatedAt,
+        ])
+        .values_panic([
+            user.into(),
+            arg.feed.into(),
+            arg.field.to_string().into(),
+            arg.pattern.clone().into(),
+            arg.is_regex.into(),
+            arg.action.to_string().into(),
+            arg.tag.clone().into(),
+            Utc::now().into(),
+        ])
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    db.execute(sql.as_str(), &*values.as_params())?;
+    Ok(db.last_insert_rowid() as i32)
+}
+
+pub fn read_all(conn: &DbConnection, user: i32) -> Result<Vec<Rule>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Rules::Id,
+            Rules::Feed,
+            Rules::Field,
+            Rules::Pattern,
+            Rules::IsRegex,
+            Rules::Action,
+            Rules::Tag,
+            Rules::Matches,
+            Rules::CreatedAt,
+        ])
+        .from(Rules::Table)
+        .and_where(Expr::col(Rules::User).eq(user))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Rule::from(x)))?;
+
+    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<Rule>>())
+}
+
+/// Reads the rules of the owner of a feed that apply to it, global ones included.
+pub fn read_all_for_feed(conn: &DbConnection, feed: i32) -> Result<Vec<Rule>> {
+    let (sql, values) = Query::select()
+        .columns([
+            (Rules::Table, Rules::Id),
+            (Rules::Table, Rules::Feed),
+            (Rules::Table, Rules::Field),
+            (Rules::Table, Rules::Pattern),
+            (Rules::Table, Rules::IsRegex),
+            (Rules::Table, Rules::Action),
+            (Rules::Table, Rules::Tag),
+            (Rules::Table, Rules::Matches),
+            (Rules::Table, Rules::CreatedAt),
+        ])
+        .from(Rules::Table)
+        .inner_join(
+            Feeds::Table,
+            Expr::col((Rules::Table, Rules::User)).equals((Feeds::Table, Feeds::User)),
+        )
+        .and_where(Expr::col((Feeds::Table, Feeds::Id)).eq(feed))
+        .cond_where(
+            Cond::any()
+                .add(Expr::col((Rules::Table, Rules::Feed)).is_null())
+                .add(Expr::col((Rules::Table, Rules::Feed)).eq(feed)),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(Rule::from(x)))?;
+
+    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<Rule>>())
+}
+
+pub fn read(conn: &DbConnection, user: i32, id: i32) -> Result<Option<Rule>> {
+    let (sql, values) = Query::select()
+        .columns([
+            Rules::Id,
+            Rules::Feed,
+            Rules::Field,
+            Rules::Pattern,
+            Rules::IsRegex,
+            Rules::Action,
+            Rules::Tag,
+            Rules::Matches,
+            Rules::CreatedAt,
+        ])
+        .from(Rules::Table)
+        .and_where(Expr::col(Rules::User).eq(user))
+        .and_where(Expr::col(Rules::Id).eq(id))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(Rule::from))
+}
+
+pub fn update(conn: &DbConnection, user: i32, arg: &RuleToUpdate) -> Result<usize> {
+    let mut vals = vec![];
+
+    if let Some(field) = &arg.field {
+        vals.push((Rules::Field, field.to_string().into()));
+    }
+
+    if let Some(pattern) = &arg.pattern {
+        vals.push((Rules::Pattern, pattern.into()));
+    }
+
+    if let Some(is_regex) = arg.is_regex {
+        vals.push((Rules::IsRegex, is_regex.into()));
+    }
+
+    if let Some(action) = &arg.action {
+        vals.push((Rules::Action, action.to_string().into()));
+    }
+
+    if let Some(tag) = &arg.tag {
+        vals.push((Rules::Tag, tag.into()));
+    }
+
+    if vals.is_empty() {
+        return Ok(0);
+    }
+
+    let (sql, values) = Query::update()
+        .table(Rules::Table)
+        .values(vals)
+        .and_where(Expr::col(Rules::User).eq(user))
+        .and_where(Expr::col(Rules::Id).eq(arg.id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn increase_matches(conn: &DbConnection, id: i32, count: usize) -> Result<usize> {
+    let (sql, values) = Query::update()
+        .table(Rules::Table)
+        .value(Rules::Matches, Expr::col(Rules::Matches).add(count as i64))
+        .and_where(Expr::col(Rules::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn delete(conn: &DbConnection, user: i32, id: i32) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Rules::Table)
+        .and_where(Expr::col(Rules::User).eq(user))
+        .and_where(Expr::col(Rules::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+/// Remembers that a rule skipped the item with the fingerprint in a feed. Returns 0 when
+/// it already had.
+pub fn remember_skip(conn: &DbConnection, id: i32, feed: i32, fingerprint: &str) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(RuleSkips::Table)
+        .columns([RuleSkips::Rule, RuleSkips::Feed, RuleSkips::Fingerprint])
+        .values_panic([id.into(), feed.into(), fingerprint.into()])
+        .on_conflict(
+            OnConflict::columns([RuleSkips::Rule, RuleSkips::Feed, RuleSkips::Fingerprint])
+                .do_nothing()
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
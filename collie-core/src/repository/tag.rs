@@ -0,0 +1,53 @@
+use sea_query::{Expr, OnConflict, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::error::Result;
+
+use super::database::{DbConnection, Items, Tags};
+
+/// Tags the item with the fingerprint in a feed, which saves looking up the id of an
+/// item that was just inserted.
+pub fn create(conn: &DbConnection, feed: i32, fingerprint: &str, name: &str) -> Result<usize> {
+    let (sql, values) = Query::insert()
+        .into_table(Tags::Table)
+        .columns([Tags::Item, Tags::Name])
+        .select_from(
+            Query::select()
+                .column(Items::Id)
+                .expr(Expr::val(name))
+                .from(Items::Table)
+                .and_where(Expr::col(Items::Feed).eq(feed))
+                .and_where(Expr::col(Items::Fingerprint).eq(fingerprint))
+                .to_owned(),
+        )?
+        .on_conflict(
+            OnConflict::columns([Tags::Item, Tags::Name])
+                .do_nothing()
+                .to_owned(),
+        )
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn read_all(conn: &DbConnection, user: i32, item: i32) -> Result<Vec<String>> {
+    let (sql, values) = Query::select()
+        .column((Tags::Table, Tags::Name))
+        .from(Tags::Table)
+        .inner_join(
+            Items::Table,
+            Expr::col((Tags::Table, Tags::Item)).equals((Items::Table, Items::Id)),
+        )
+        .and_where(Expr::col((Items::Table, Items::User)).eq(user))
+        .and_where(Expr::col((Items::Table, Items::Id)).eq(item))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| x.get::<_, String>(0))?;
+
+    Ok(rows
+        .map(std::result::Result::unwrap)
+        .collect::<Vec<String>>())
+}
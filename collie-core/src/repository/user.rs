@@ -0,0 +1,79 @@
+use chrono::Utc;
+use sea_query::{Expr, Query, SqliteQueryBuilder};
+use sea_query_rusqlite::RusqliteBinder;
+
+use crate::{
+    error::Result,
+    model::user::{User, UserToCreate},
+};
+
+use super::database::{DbConnection, Feeds, Items, Users};
+
+pub fn create(conn: &DbConnection, arg: &UserToCreate) -> Result<i32> {
+    let (sql, values) = Query::insert()
+        .into_table(Users::Table)
+        .columns([Users::Name, Users::CreatedAt])
+        .values_panic([(*arg.name).into(), Utc::now().into()])
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    db.execute(sql.as_str(), &*values.as_params())?;
+    Ok(db.last_insert_rowid() as i32)
+}
+
+pub fn read_all(conn: &DbConnection) -> Result<Vec<User>> {
+    let (sql, values) = Query::select()
+        .columns([Users::Id, Users::Name, Users::CreatedAt])
+        .from(Users::Table)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let rows = stmt.query_map(&*values.as_params(), |x| Ok(User::from(x)))?;
+
+    Ok(rows.map(std::result::Result::unwrap).collect::<Vec<User>>())
+}
+
+pub fn read(conn: &DbConnection, id: i32) -> Result<Option<User>> {
+    let (sql, values) = Query::select()
+        .columns([Users::Id, Users::Name, Users::CreatedAt])
+        .from(Users::Table)
+        .and_where(Expr::col(Users::Id).eq(id))
+        .limit(1)
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let mut stmt = db.prepare(sql.as_str())?;
+    let mut rows = stmt.query(&*values.as_params())?;
+
+    Ok(rows.next()?.map(User::from))
+}
+
+pub fn delete(conn: &DbConnection, id: i32) -> Result<usize> {
+    let (sql, values) = Query::delete()
+        .from_table(Users::Table)
+        .and_where(Expr::col(Users::Id).eq(id))
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    Ok(db.execute(sql.as_str(), &*values.as_params())?)
+}
+
+pub fn adopt(conn: &DbConnection, id: i32) -> Result<usize> {
+    let (feeds_sql, feeds_values) = Query::update()
+        .table(Feeds::Table)
+        .values([(Feeds::User, id.into())])
+        .and_where(Expr::col(Feeds::User).is_null())
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let (items_sql, items_values) = Query::update()
+        .table(Items::Table)
+        .values([(Items::User, id.into())])
+        .and_where(Expr::col(Items::User).is_null())
+        .build_rusqlite(SqliteQueryBuilder);
+
+    let db = conn.lock().unwrap();
+    let adopted = db.execute(feeds_sql.as_str(), &*feeds_values.as_params())?;
+    db.execute(items_sql.as_str(), &*items_values.as_params())?;
+    Ok(adopted)
+}
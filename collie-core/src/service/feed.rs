@@ -1,77 +1,466 @@
+use chrono::{DateTime, FixedOffset};
 use scraper::{Html, Selector};
 
 use crate::{
     error::{Error, Result},
     model::{
-        feed::{Feed, FeedToCreate, FeedToUpdate},
+        feed::{
+            Feed, FeedCandidate, FeedFormat, FeedKind, FeedMetadata, FeedToCreate, FeedToUpdate,
+            ScrapeSelectors,
+        },
         syndication::Feed as SyndicationFeed,
     },
     repository::{database::DbConnection, feed},
-    util::fetcher,
+    service::item,
+    util::{fetcher, link, resolver::Registry},
 };
 
-pub async fn create(conn: &DbConnection, arg: &FeedToCreate, proxy: Option<&str>) -> Result<usize> {
+pub async fn create(
+    conn: &DbConnection,
+    user: i32,
+    arg: &FeedToCreate,
+    proxy: Option<&str>,
+) -> Result<usize> {
+    create_with(conn, user, arg, proxy, &Registry::default()).await
+}
+
+/// Creates a feed, letting the given resolvers map known sites to their feeds.
+pub async fn create_with(
+    conn: &DbConnection,
+    user: i32,
+    arg: &FeedToCreate,
+    proxy: Option<&str>,
+    registry: &Registry,
+) -> Result<usize> {
     if arg.link.is_empty() {
         return Err(Error::BadArgument);
     }
 
-    let html_content = fetcher::get(&arg.link, proxy).await.unwrap();
-    let is_feed = html_content.parse::<SyndicationFeed>().is_ok();
+    let content = fetcher::get(&arg.link, proxy).await?;
+    if let Some(selectors) = &arg.selectors {
+        return create_scraped(conn, user, arg, &content, selectors);
+    }
 
-    let link = if is_feed {
-        arg.link.clone()
-    } else if let Some(link) = parse_link(&html_content)? {
-        link
+    // A link to the feed itself is parsed from the body already fetched.
+    let (link, syndication) = if let Ok(syndication) = content.parse::<SyndicationFeed>() {
+        (arg.link.clone(), syndication)
+    } else if let Some(candidate) = discover_in(&content, &arg.link, proxy, registry)
+        .await
+        .into_iter()
+        .find(|x| x.format != FeedFormat::Json)
+    {
+        let syndication = fetcher::get(&candidate.link, proxy)
+            .await?
+            .parse::<SyndicationFeed>()?;
+        (candidate.link, syndication)
     } else {
         return Err(Error::FeedNotFound);
     };
 
-    let title = fetch_title(&link, proxy).await?;
+    let metadata = parse_metadata(&link, &syndication);
 
     let arg = FeedToCreate {
-        title,
+        title: syndication_title(&syndication),
         link,
         fetch_old_items: arg.fetch_old_items,
+        selectors: None,
     };
 
-    feed::create(conn, &arg)
+    feed::create(conn, user, &arg, &FeedKind::Syndication, &metadata)
 }
 
-pub fn read_all(conn: &DbConnection) -> Result<Vec<Feed>> {
-    feed::read_all(conn)
+/// Creates a feed of a page without one, as long as the selectors find items in it.
+fn create_scraped(
+    conn: &DbConnection,
+    user: i32,
+    arg: &FeedToCreate,
+    html: &str,
+    selectors: &ScrapeSelectors,
+) -> Result<usize> {
+    if item::scrape(&arg.link, html, selectors)?.is_empty() {
+        return Err(Error::FeedNotFound);
+    }
+
+    let title = if arg.title.trim().is_empty() {
+        parse_title(html).unwrap_or_else(|| arg.link.clone())
+    } else {
+        arg.title.trim().to_string()
+    };
+    let metadata = FeedMetadata {
+        site_url: Some(arg.link.clone()),
+        ..Default::default()
+    };
+
+    let arg = FeedToCreate {
+        title,
+        link: arg.link.clone(),
+        fetch_old_items: arg.fetch_old_items,
+        selectors: Some(selectors.clone()),
+    };
+
+    feed::create(conn, user, &arg, &FeedKind::Scraped, &metadata)
 }
 
-pub fn read(conn: &DbConnection, id: i32) -> Result<Option<Feed>> {
-    feed::read(conn, id)
+/// Creates a feed read by the source registered under `kind` with the worker. Nothing is
+/// fetched, so the link is whatever the source understands, and it names the feed unless
+/// a title is given.
+pub fn create_custom(
+    conn: &DbConnection,
+    user: i32,
+    arg: &FeedToCreate,
+    kind: &str,
+) -> Result<usize> {
+    let kind = kind.trim().parse::<FeedKind>()?;
+    if arg.link.is_empty() || !matches!(kind, FeedKind::Custom(_)) {
+        return Err(Error::BadArgument);
+    }
+
+    let title = if arg.title.trim().is_empty() {
+        arg.link.clone()
+    } else {
+        arg.title.trim().to_string()
+    };
+
+    let arg = FeedToCreate {
+        title,
+        link: arg.link.clone(),
+        fetch_old_items: arg.fetch_old_items,
+        selectors: None,
+    };
+
+    feed::create(conn, user, &arg, &kind, &FeedMetadata::default())
 }
 
-pub fn update(conn: &DbConnection, arg: &FeedToUpdate) -> Result<usize> {
-    feed::update(conn, arg)
+pub fn read_all(conn: &DbConnection, user: i32) -> Result<Vec<Feed>> {
+    feed::read_all(conn, user)
+}
+
+pub fn read_all_subscribed(conn: &DbConnection) -> Result<Vec<Feed>> {
+    feed::read_all_subscribed(conn)
+}
+
+pub fn read(conn: &DbConnection, user: i32, id: i32) -> Result<Option<Feed>> {
+    feed::read(conn, user, id)
+}
+
+pub fn update(conn: &DbConnection, user: i32, arg: &FeedToUpdate) -> Result<usize> {
+    let mut count = 0;
+    if let Some(selectors) = &arg.selectors {
+        item::validate_selectors(selectors)?;
+        if let Some(x) = feed::read(conn, user, arg.id)?.filter(|x| x.kind == FeedKind::Scraped) {
+            count = feed::upsert_selectors(conn, x.id, selectors)?;
+        }
+    }
+
+    Ok(feed::update(conn, user, arg)?.max(count))
+}
+
+pub fn read_selectors(conn: &DbConnection, feed: i32) -> Result<Option<ScrapeSelectors>> {
+    feed::read_selectors(conn, feed)
+}
+
+/// Reads the fingerprint of the item that was on top of a scraped feed at its last poll.
+/// Undated items above it on the page are new, and those below it were seen already.
+pub fn read_seen_mark(conn: &DbConnection, feed: i32) -> Result<Option<String>> {
+    feed::read_seen_mark(conn, feed)
+}
+
+pub fn update_seen_mark(conn: &DbConnection, feed: i32, mark: &str) -> Result<usize> {
+    feed::update_seen_mark(conn, feed, mark)
+}
+
+pub fn update_checked_at(
+    conn: &DbConnection,
+    ids: &[i32],
+    checked_at: DateTime<FixedOffset>,
+) -> Result<usize> {
+    feed::update_checked_at(conn, ids, checked_at)
+}
+
+/// Refreshes the metadata of feeds sharing the same link.
+pub fn update_metadata(conn: &DbConnection, ids: &[i32], metadata: &FeedMetadata) -> Result<usize> {
+    feed::update_metadata(conn, ids, metadata)
+}
+
+pub fn delete(conn: &DbConnection, user: i32, id: i32) -> Result<usize> {
+    feed::delete(conn, user, id)
+}
+
+/// Finds the feed a page links to, resolved against its `<base>` or the page URL.
+pub fn parse_link(html: &str, page: &str) -> Result<Option<String>> {
+    Ok(parse_candidates(html, page)
+        .into_iter()
+        .find(|x| x.format != FeedFormat::Json)
+        .map(|x| x.link))
+}
+
+/// Finds every feed that can be subscribed to from a link. A link to a feed gives only
+/// that feed. For a page, the feeds it declares with `<link rel="alternate">` are
+/// returned, or failing that, the feeds among its `<a>` links and common feed paths.
+pub async fn discover(link: &str, proxy: Option<&str>) -> Result<Vec<FeedCandidate>> {
+    discover_with(link, proxy, &Registry::default()).await
+}
+
+pub async fn discover_with(
+    link: &str,
+    proxy: Option<&str>,
+    registry: &Registry,
+) -> Result<Vec<FeedCandidate>> {
+    let content = fetcher::get(link, proxy).await?;
+
+    if let Some(candidate) = sniff(link, &content) {
+        return Ok(vec![candidate]);
+    }
+
+    Ok(discover_in(&content, link, proxy, registry).await)
 }
 
-pub fn delete(conn: &DbConnection, id: i32) -> Result<usize> {
-    feed::delete(conn, id)
+/// Reads the feeds a page declares with `<link rel="alternate">`.
+pub fn parse_candidates(html: &str, page: &str) -> Vec<FeedCandidate> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("link[rel][type][href]").unwrap();
+    let base = page_base(&document, page);
+
+    let mut candidates: Vec<FeedCandidate> = vec![];
+    for element in document.select(&selector) {
+        let element = element.value();
+        let is_alternate = element
+            .attr("rel")
+            .unwrap_or_default()
+            .split_ascii_whitespace()
+            .any(|x| x.eq_ignore_ascii_case("alternate"));
+        let format = element.attr("type").and_then(FeedFormat::from_mime);
+
+        if let (true, Some(format), Some(href)) = (is_alternate, format, element.attr("href")) {
+            let link = resolve(base.as_deref(), href);
+            if candidates.iter().all(|x| x.link != link) {
+                candidates.push(FeedCandidate {
+                    link,
+                    title: element
+                        .attr("title")
+                        .map(|x| x.trim().to_string())
+                        .filter(|x| !x.is_empty()),
+                    format,
+                });
+            }
+        }
+    }
+
+    candidates
 }
 
-pub fn parse_link(html: &str) -> Result<Option<String>> {
+/// Reads `<a>` links of a page that look like they point at feeds. They still need to be
+/// fetched to tell whether they really do.
+pub fn parse_anchor_links(html: &str, page: &str) -> Vec<String> {
     let document = Html::parse_document(html);
-    let selector =
-        Selector::parse("link[type='application/rss+xml'], link[type='application/atom+xml']")
-            .unwrap();
+    let selector = Selector::parse("a[href]").unwrap();
+    let base = page_base(&document, page);
 
+    let mut links: Vec<String> = vec![];
     for element in document.select(&selector) {
-        if let Some(href) = element.value().attr("href") {
-            return Ok(Some(href.to_string()));
+        let Some(href) = element.value().attr("href") else {
+            continue;
+        };
+
+        let link = resolve(base.as_deref(), href);
+        if looks_like_feed(&link) && !links.contains(&link) {
+            links.push(link);
         }
     }
 
-    Ok(None)
+    links.truncate(MAX_PROBES);
+    links
+}
+
+const COMMON_PATHS: [&str; 4] = ["/feed", "/rss.xml", "/atom.xml", "/index.xml"];
+
+const MAX_PROBES: usize = 10;
+
+async fn discover_in(
+    html: &str,
+    page: &str,
+    proxy: Option<&str>,
+    registry: &Registry,
+) -> Vec<FeedCandidate> {
+    // Known sites come first, since the feeds their pages declare, if any, tend to be
+    // less useful than the official endpoints.
+    let mut candidates = registry.resolve(page, html).into_iter().collect::<Vec<_>>();
+    for candidate in parse_candidates(html, page) {
+        if candidates.iter().all(|x| x.link != candidate.link) {
+            candidates.push(candidate);
+        }
+    }
+    if !candidates.is_empty() {
+        return candidates;
+    }
+
+    let mut candidates: Vec<FeedCandidate> = vec![];
+    for link in parse_anchor_links(html, page) {
+        if let Some(candidate) = probe(&link, proxy).await {
+            if candidates.iter().all(|x| x.link != candidate.link) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    if !candidates.is_empty() {
+        return candidates;
+    }
+
+    if link::is_absolute(page) {
+        for path in COMMON_PATHS {
+            if let Some(candidate) = probe(&link::resolve(page, path), proxy).await {
+                candidates.push(candidate);
+            }
+        }
+    }
+
+    candidates
+}
+
+async fn probe(link: &str, proxy: Option<&str>) -> Option<FeedCandidate> {
+    let content = fetcher::get(link, proxy).await.ok()?;
+    sniff(link, &content)
+}
+
+/// Tells whether the content is a feed, and which kind.
+fn sniff(link: &str, content: &str) -> Option<FeedCandidate> {
+    if let Ok(feed) = content.parse::<SyndicationFeed>() {
+        let (title, format) = match feed {
+            SyndicationFeed::Atom(atom) => (atom.title().to_string(), FeedFormat::Atom),
+            SyndicationFeed::RSS(rss) => (rss.title().to_string(), FeedFormat::Rss),
+        };
+        return Some(FeedCandidate {
+            link: link.to_string(),
+            title: Some(title).filter(|x| !x.is_empty()),
+            format,
+        });
+    }
+
+    let json = serde_json::from_str::<serde_json::Value>(content).ok()?;
+    let is_json_feed = json["version"]
+        .as_str()
+        .is_some_and(|x| x.starts_with("https://jsonfeed.org/version/"));
+
+    is_json_feed.then(|| FeedCandidate {
+        link: link.to_string(),
+        title: json["title"].as_str().map(str::to_string),
+        format: FeedFormat::Json,
+    })
+}
+
+fn looks_like_feed(link: &str) -> bool {
+    let path = link
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/')
+        .to_lowercase();
+    let name = path.rsplit('/').next().unwrap_or_default();
+
+    matches!(name, "feed" | "rss" | "atom")
+        || name.ends_with(".rss")
+        || name.ends_with(".atom")
+        || ((name.ends_with(".xml") || name.ends_with(".json"))
+            && ["feed", "rss", "atom", "index"]
+                .iter()
+                .any(|x| name.contains(x)))
+}
+
+fn page_base(document: &Html, page: &str) -> Option<String> {
+    let selector = Selector::parse("base[href]").unwrap();
+    let base = document
+        .select(&selector)
+        .next()
+        .and_then(|x| x.value().attr("href"));
+
+    link::base(&[base, Some(page)])
+}
+
+fn parse_title(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("title").unwrap();
+
+    document
+        .select(&selector)
+        .next()
+        .map(|x| x.text().collect::<String>().trim().to_string())
+        .filter(|x| !x.is_empty())
+}
+
+fn resolve(base: Option<&str>, href: &str) -> String {
+    match base {
+        Some(base) => link::resolve(base, href),
+        None => href.trim().to_string(),
+    }
 }
 
 pub async fn fetch_title(link: &str, proxy: Option<&str>) -> Result<String> {
     let content = fetcher::get(link, proxy).await?;
-    match content.parse::<SyndicationFeed>()? {
-        SyndicationFeed::Atom(atom) => Ok(atom.title().to_string()),
-        SyndicationFeed::RSS(rss) => Ok(rss.title().to_string()),
+    Ok(syndication_title(&content.parse::<SyndicationFeed>()?))
+}
+
+/// Reads what a feed tells about itself, with links resolved against the feed link.
+pub fn parse_metadata(link: &str, feed: &SyndicationFeed) -> FeedMetadata {
+    let metadata = match feed {
+        SyndicationFeed::Atom(atom) => {
+            let base = link::base(&[atom.base(), Some(link)]);
+            let site_url = atom
+                .links()
+                .iter()
+                .find(|x| x.rel() == "alternate")
+                .map(|x| resolve(base.as_deref(), x.href()));
+            let authors = atom
+                .authors()
+                .iter()
+                .map(|x| x.name().trim())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            FeedMetadata {
+                site_url,
+                description: atom.subtitle().map(|x| x.as_str().to_string()),
+                image: atom
+                    .logo()
+                    .or(atom.icon())
+                    .map(|x| resolve(base.as_deref(), x)),
+                language: atom.lang().map(str::to_string),
+                author: Some(authors),
+            }
+        }
+        SyndicationFeed::RSS(rss) => {
+            // Feeds without a site leave the link empty rather than out.
+            let site_url = Some(rss.link().trim())
+                .filter(|x| !x.is_empty())
+                .map(|x| resolve(Some(link), x));
+
+            FeedMetadata {
+                image: rss
+                    .image()
+                    .map(|x| resolve(site_url.as_deref().or(Some(link)), x.url())),
+                site_url,
+                description: Some(rss.description().to_string()),
+                language: rss.language().map(str::to_string),
+                author: rss
+                    .managing_editor()
+                    .map(str::to_string)
+                    .or(rss.dublin_core_ext().map(|x| x.creators().join(","))),
+            }
+        }
+    };
+
+    let clean = |x: Option<String>| x.map(|x| x.trim().to_string()).filter(|x| !x.is_empty());
+    FeedMetadata {
+        site_url: clean(metadata.site_url),
+        description: clean(metadata.description),
+        image: clean(metadata.image),
+        language: clean(metadata.language),
+        author: clean(metadata.author),
+    }
+}
+
+fn syndication_title(feed: &SyndicationFeed) -> String {
+    match feed {
+        SyndicationFeed::Atom(atom) => atom.title().to_string(),
+        SyndicationFeed::RSS(rss) => rss.title().to_string(),
     }
 }
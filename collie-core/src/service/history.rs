@@ -0,0 +1,29 @@
+use chrono::{Duration, Utc};
+
+use crate::{
+    error::Result,
+    model::item::{Item, ItemOrder, ItemReadOption, ItemStatus},
+    repository::{database::DbConnection, history, item},
+};
+
+/// Takes back the change of status made by a bulk update, returning how many items were
+/// restored. Items whose status changed again since are left as they are.
+pub fn undo(conn: &DbConnection, user: i32, batch: &str) -> Result<usize> {
+    history::undo(conn, user, batch)
+}
+
+/// Lists the items the user read most recently first.
+pub fn recently_read(conn: &DbConnection, user: i32, limit: u64) -> Result<Vec<Item>> {
+    let opt = ItemReadOption {
+        status: Some(ItemStatus::Read),
+        order_by: Some(ItemOrder::ReadDateDesc),
+        limit: Some(limit),
+        ..Default::default()
+    };
+    item::read_all(conn, user, &opt)
+}
+
+/// Forgets the batches older than `window`, which can no longer be undone afterwards.
+pub fn prune(conn: &DbConnection, window: Duration) -> Result<usize> {
+    history::delete_before(conn, (Utc::now() - window).fixed_offset())
+}
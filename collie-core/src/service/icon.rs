@@ -0,0 +1,134 @@
+use chrono::{Duration, Utc};
+use scraper::{Html, Selector};
+
+use crate::{
+    error::Result,
+    model::feed::Feed,
+    repository::{database::DbConnection, icon},
+    util::{fetcher, link},
+};
+
+/// Returns the content type and bytes of the icon of a feed.
+pub fn icon(conn: &DbConnection, user: i32, feed: i32) -> Result<Option<(String, Vec<u8>)>> {
+    icon::read(conn, user, feed)
+}
+
+/// Looks for the icons of feeds that haven't been checked for longer than `interval`.
+/// Feeds sharing a link share a single lookup. Returns the number of feeds that have an
+/// icon now.
+pub async fn refresh_all(
+    conn: &DbConnection,
+    interval: Duration,
+    proxy: Option<&str>,
+) -> Result<usize> {
+    let now = Utc::now().fixed_offset();
+    let feeds = icon::read_all_stale(conn, now - interval)?;
+
+    let mut links: Vec<(String, Vec<Feed>)> = vec![];
+    for x in feeds {
+        match links.iter_mut().find(|(link, _)| *link == x.link) {
+            Some((_, feeds)) => feeds.push(x),
+            None => links.push((x.link.clone(), vec![x])),
+        }
+    }
+
+    let mut found = 0;
+    for (_, feeds) in links {
+        let id = match fetch(&feeds[0], proxy).await {
+            Some((mime, data)) => Some(icon::create(conn, &mime, &data)?),
+            None => None,
+        };
+        for feed in &feeds {
+            icon::attach(conn, feed.id, id, now)?;
+            found += usize::from(id.is_some());
+        }
+    }
+    icon::delete_unused(conn)?;
+
+    Ok(found)
+}
+
+/// Tries the image the feed declares, then the icon its site declares, then the
+/// `/favicon.ico` of the site.
+pub async fn fetch(feed: &Feed, proxy: Option<&str>) -> Option<(String, Vec<u8>)> {
+    let site = feed.metadata.site_url.as_deref().unwrap_or(&feed.link);
+
+    let mut candidates = feed.metadata.image.iter().cloned().collect::<Vec<_>>();
+    if let Ok(html) = fetcher::get(site, proxy).await {
+        candidates.extend(parse_icon_link(&html, site));
+    }
+    if link::is_absolute(site) {
+        candidates.push(link::resolve(site, "/favicon.ico"));
+    }
+
+    for candidate in candidates {
+        if let Ok((mime, data)) = fetcher::get_bytes(&candidate, proxy).await {
+            let mime = mime
+                .filter(|x| x.starts_with("image/"))
+                .or_else(|| sniff(&data));
+            if let (Some(mime), false) = (mime, data.is_empty()) {
+                return Some((mime, data));
+            }
+        }
+    }
+
+    None
+}
+
+/// Reads the icon a page declares, preferring `rel="icon"` over touch icons.
+pub fn parse_icon_link(html: &str, page: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("link[rel][href]").unwrap();
+    let base_selector = Selector::parse("base[href]").unwrap();
+
+    let base = document
+        .select(&base_selector)
+        .next()
+        .and_then(|x| x.value().attr("href"));
+    let base = link::base(&[base, Some(page)]);
+
+    let rels = |element: &scraper::ElementRef| {
+        element
+            .value()
+            .attr("rel")
+            .unwrap_or_default()
+            .to_lowercase()
+            .split_ascii_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let links = document.select(&selector).collect::<Vec<_>>();
+    let href = links
+        .iter()
+        .find(|x| rels(x).iter().any(|x| x == "icon"))
+        .or(links
+            .iter()
+            .find(|x| rels(x).iter().any(|x| x.starts_with("apple-touch-icon"))))
+        .and_then(|x| x.value().attr("href"))?;
+
+    Some(match base {
+        Some(base) => link::resolve(&base, href),
+        None => href.trim().to_string(),
+    })
+}
+
+/// Guesses the type of an image from its first bytes, for servers that don't tell it.
+fn sniff(data: &[u8]) -> Option<String> {
+    let mime = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        "image/gif"
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if data.starts_with(b"\x00\x00\x01\x00") {
+        "image/x-icon"
+    } else if data.len() > 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        "image/webp"
+    } else if String::from_utf8_lossy(&data[..data.len().min(512)]).contains("<svg") {
+        "image/svg+xml"
+    } else {
+        return None;
+    };
+
+    Some(mime.to_string())
+}
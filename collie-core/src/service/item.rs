@@ -1,82 +1,306 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use rand::Rng;
+use scraper::{ElementRef, Html, Selector};
 
 use crate::{
-    error::Result,
+    error::{Error, Result},
     model::{
-        item::{Item, ItemReadOption, ItemToCreate, ItemToUpdate, ItemToUpdateAll},
+        feed::ScrapeSelectors,
+        item::{
+            DailyItemCounts, FeedItemCounts, Item, ItemBatch, ItemPage, ItemReadOption,
+            ItemToCreate, ItemToUpdate, ItemToUpdateAll,
+        },
         syndication::{Feed as SyndicationFeed, RawItem},
     },
     repository::{database::DbConnection, item},
-    util::fetcher,
+    util::{
+        fetcher, link,
+        sanitizer::{self, SanitizePolicy},
+    },
 };
 
 pub fn create(conn: &DbConnection, arg: &ItemToCreate) -> Result<usize> {
     item::create(conn, arg)
 }
 
-pub fn read_all(conn: &DbConnection, opt: &ItemReadOption) -> Result<Vec<Item>> {
-    item::read_all(conn, opt)
+pub fn read_all(conn: &DbConnection, user: i32, opt: &ItemReadOption) -> Result<Vec<Item>> {
+    let mut items = item::read_all(conn, user, opt)?;
+    if opt.sanitize {
+        sanitize(&mut items);
+    }
+
+    Ok(items)
+}
+
+/// Reads a page of items. Pass its `prev` or `next` cursor as `before` or `after` to read
+/// the page before or after it, which stays the same while new items come in.
+pub fn read_page(conn: &DbConnection, user: i32, opt: &ItemReadOption) -> Result<ItemPage> {
+    let mut page = item::read_page(conn, user, opt)?;
+    if opt.sanitize {
+        sanitize(&mut page.items);
+    }
+
+    Ok(page)
+}
+
+pub fn count_all(conn: &DbConnection, user: i32, opt: &ItemReadOption) -> Result<i64> {
+    item::count_all(conn, user, opt)
+}
+
+/// Counts unread, saved and all items of every feed at once, for listing feeds.
+pub fn counts_by_feed(conn: &DbConnection, user: i32) -> Result<Vec<FeedItemCounts>> {
+    item::counts_by_feed(conn, user)
+}
+
+pub fn counts_by_day(
+    conn: &DbConnection,
+    user: i32,
+    opt: &ItemReadOption,
+) -> Result<Vec<DailyItemCounts>> {
+    item::counts_by_day(conn, user, opt)
+}
+
+pub fn latest_published_at(
+    conn: &DbConnection,
+    feed: i32,
+) -> Result<Option<DateTime<FixedOffset>>> {
+    item::latest_published_at(conn, feed)
 }
 
-pub fn count_all(conn: &DbConnection, opt: &ItemReadOption) -> Result<i64> {
-    item::count_all(conn, opt)
+pub fn update(conn: &DbConnection, user: i32, arg: &ItemToUpdate) -> Result<usize> {
+    item::update(conn, user, arg)
 }
 
-pub fn update(conn: &DbConnection, arg: &ItemToUpdate) -> Result<usize> {
-    item::update(conn, arg)
+pub fn update_content(
+    conn: &DbConnection,
+    feed: i32,
+    fingerprint: &str,
+    content: &str,
+) -> Result<usize> {
+    item::update_content(conn, feed, fingerprint, content)
 }
 
-pub fn update_all(conn: &DbConnection, arg: &ItemToUpdateAll) -> Result<usize> {
-    item::update_all(conn, arg)
+pub fn update_all(conn: &DbConnection, user: i32, arg: &ItemToUpdateAll) -> Result<usize> {
+    Ok(update_all_with_batch(conn, user, arg)?.count)
 }
 
+/// Updates every item matching an option, returning the batch to pass to
+/// `history::undo` to take the change of status back.
+pub fn update_all_with_batch(
+    conn: &DbConnection,
+    user: i32,
+    arg: &ItemToUpdateAll,
+) -> Result<ItemBatch> {
+    let id = format!("{:016x}", rand::thread_rng().gen::<u64>());
+    let count = item::update_all(conn, user, arg, &id)?;
+    Ok(ItemBatch { id, count })
+}
+
+/// Fetches the items of a feed, resolving relative links in them against `xml:base`, the
+/// alternate link of the feed, or the feed link itself, whichever is found first.
 pub async fn fetch(link: &str, proxy: Option<&str>) -> Result<Vec<RawItem>> {
     let content = fetcher::get(link, proxy).await?;
-    match content.parse::<SyndicationFeed>()? {
-        SyndicationFeed::Atom(atom) => Ok(atom
-            .entries()
-            .iter()
-            .map(|x| RawItem {
-                title: x.title().to_string(),
-                author: Some(
-                    x.authors()
+    Ok(parse(link, &content.parse::<SyndicationFeed>()?))
+}
+
+/// Reads the items of a feed fetched from `link`.
+pub fn parse(link: &str, feed: &SyndicationFeed) -> Vec<RawItem> {
+    match feed {
+        SyndicationFeed::Atom(atom) => {
+            let alternate = atom
+                .links()
+                .iter()
+                .find(|x| x.rel() == "alternate")
+                .map(|x| x.href());
+            let base = link::base(&[atom.base(), alternate, Some(link)]);
+
+            atom.entries()
+                .iter()
+                .map(|x| RawItem {
+                    title: x.title().to_string(),
+                    author: Some(
+                        x.authors()
+                            .iter()
+                            .map(|x| x.name().trim())
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    ),
+                    link: x
+                        .links()
                         .iter()
-                        .map(|x| x.name().trim())
-                        .collect::<Vec<_>>()
-                        .join(","),
-                ),
-                link: x.links().first().map(|x| x.href().trim().to_string()),
-                content: x
-                    .content()
-                    .map(atom_syndication::Content::value)
-                    .filter(std::option::Option::is_some)
-                    .map(|x| x.unwrap().trim().to_string()),
-                published_at: x
-                    .published()
-                    .or(Some(x.updated()))
-                    .map(|x| x.with_timezone(&Utc).fixed_offset()),
-            })
-            .collect()),
-        SyndicationFeed::RSS(rss) => Ok(rss
-            .items()
-            .iter()
-            .map(|x| RawItem {
-                title: x.title().unwrap_or("Untitled").trim().to_string(),
-                author: x
-                    .author()
-                    .map(|x| x.trim().to_string())
-                    .or(x.dublin_core_ext().map(|x| x.creators().join(","))),
-                link: x.link().map(std::string::ToString::to_string),
-                content: x.description().map(std::string::ToString::to_string),
-                published_at: x
-                    .pub_date()
-                    .map(|x| {
-                        DateTime::parse_from_rfc2822(x)
-                            .map(|x| x.with_timezone(&Utc).fixed_offset())
-                    })
-                    .filter(std::result::Result::is_ok)
-                    .map(std::result::Result::unwrap),
+                        .find(|x| x.rel() == "alternate")
+                        .or(x.links().first())
+                        .map(|x| resolve(base.as_deref(), x.href().trim())),
+                    // Items were known by their first link before the alternate one was
+                    // preferred.
+                    source_link: x.links().first().map(|x| x.href().trim().to_string()),
+                    content: x.content().and_then(|content| {
+                        let base = link::base(&[content.base(), base.as_deref()]);
+                        content
+                            .value()
+                            .map(|x| resolve_html(base.as_deref(), x.trim()))
+                    }),
+                    published_at: x
+                        .published()
+                        .or(Some(x.updated()))
+                        .map(|x| x.with_timezone(&Utc).fixed_offset()),
+                })
+                .collect()
+        }
+        SyndicationFeed::RSS(rss) => {
+            let base = link::base(&[Some(rss.link()), Some(link)]);
+
+            rss.items()
+                .iter()
+                .map(|x| RawItem {
+                    title: x.title().unwrap_or("Untitled").trim().to_string(),
+                    author: x
+                        .author()
+                        .map(|x| x.trim().to_string())
+                        .or(x.dublin_core_ext().map(|x| x.creators().join(","))),
+                    link: x.link().map(|x| resolve(base.as_deref(), x)),
+                    source_link: x.link().map(str::to_string),
+                    content: x.description().map(|x| resolve_html(base.as_deref(), x)),
+                    published_at: x
+                        .pub_date()
+                        .map(|x| {
+                            DateTime::parse_from_rfc2822(x)
+                                .map(|x| x.with_timezone(&Utc).fixed_offset())
+                        })
+                        .filter(std::result::Result::is_ok)
+                        .map(std::result::Result::unwrap),
+                })
+                .collect()
+        }
+    }
+}
+
+/// Reads the items of a page with the selectors of a scraped feed. Relative links are
+/// resolved against the `<base>` of the page or `link`, and containers without a title
+/// are skipped.
+pub fn scrape(link: &str, html: &str, selectors: &ScrapeSelectors) -> Result<Vec<RawItem>> {
+    let selectors = compile(selectors)?;
+    let document = Html::parse_document(html);
+    let base_selector = Selector::parse("base[href]").unwrap();
+    let base = link::base(&[
+        document
+            .select(&base_selector)
+            .next()
+            .and_then(|x| x.value().attr("href")),
+        Some(link),
+    ]);
+
+    Ok(document
+        .select(&selectors.container)
+        .filter_map(|container| {
+            let first = |selector: &Selector| container.select(selector).next();
+
+            let title = first(&selectors.title)
+                .map(|x| text(&x))
+                .filter(|x| !x.is_empty())?;
+            let link = first(&selectors.link)
+                .map(|x| {
+                    x.value()
+                        .attr("href")
+                        .map_or_else(|| text(&x), str::to_string)
+                })
+                .filter(|x| !x.trim().is_empty())
+                .map(|x| resolve(base.as_deref(), x.trim()));
+            let content = selectors
+                .content
+                .as_ref()
+                .and_then(first)
+                .map(|x| resolve_html(base.as_deref(), x.inner_html().trim()));
+            let published_at = selectors.date.as_ref().and_then(first).and_then(|x| {
+                parse_date(
+                    x.value()
+                        .attr("datetime")
+                        .map_or_else(|| text(&x), str::to_string),
+                )
+            });
+
+            Some(RawItem {
+                title,
+                author: None,
+                link,
+                source_link: None,
+                content,
+                published_at,
             })
-            .collect()),
+        })
+        .collect())
+}
+
+/// Checks that every selector of a scraped feed is valid CSS.
+pub fn validate_selectors(selectors: &ScrapeSelectors) -> Result<()> {
+    compile(selectors).map(|_| ())
+}
+
+struct Selectors {
+    container: Selector,
+    title: Selector,
+    link: Selector,
+    date: Option<Selector>,
+    content: Option<Selector>,
+}
+
+fn compile(selectors: &ScrapeSelectors) -> Result<Selectors> {
+    let parse = |x: &str| Selector::parse(x).map_err(|_| Error::InvalidSelector(x.to_string()));
+
+    Ok(Selectors {
+        container: parse(&selectors.container)?,
+        title: parse(&selectors.title)?,
+        link: parse(&selectors.link)?,
+        date: selectors.date.as_deref().map(parse).transpose()?,
+        content: selectors.content.as_deref().map(parse).transpose()?,
+    })
+}
+
+fn text(element: &ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reads the dates pages commonly show, which are taken as UTC when they have no offset.
+fn parse_date(x: String) -> Option<DateTime<FixedOffset>> {
+    let x = x.trim();
+
+    DateTime::parse_from_rfc3339(x)
+        .or_else(|_| DateTime::parse_from_rfc2822(x))
+        .map(|x| x.with_timezone(&Utc).fixed_offset())
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(x, "%Y-%m-%d")
+                .ok()
+                .and_then(|x| x.and_hms_opt(0, 0, 0))
+                .map(|x| x.and_utc().fixed_offset())
+        })
+}
+
+fn sanitize(items: &mut [Item]) {
+    let policy = SanitizePolicy::default();
+    for item in items {
+        item.description = sanitizer::sanitize(&item.description, &policy);
+        item.content = item
+            .content
+            .as_deref()
+            .map(|x| sanitizer::sanitize(x, &policy));
+    }
+}
+
+fn resolve(base: Option<&str>, x: &str) -> String {
+    match base {
+        Some(base) => link::resolve(base, x),
+        None => x.to_string(),
+    }
+}
+
+fn resolve_html(base: Option<&str>, x: &str) -> String {
+    match base {
+        Some(base) => link::resolve_html(x, base),
+        None => x.to_string(),
     }
 }
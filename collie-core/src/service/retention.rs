@@ -0,0 +1,70 @@
+use chrono::{Duration, Utc};
+use std::collections::HashSet;
+
+use crate::{
+    error::{Error, Result},
+    model::retention::{PurgeToCreate, RetentionPolicy},
+    repository::{database::DbConnection, feed, retention},
+};
+
+/// Overrides the global retention policy for a feed of the user.
+pub fn set(conn: &DbConnection, user: i32, feed: i32, policy: &RetentionPolicy) -> Result<usize> {
+    ensure_owner(conn, user, feed)?;
+    retention::upsert(conn, feed, policy)
+}
+
+pub fn read(conn: &DbConnection, user: i32, feed: i32) -> Result<Option<RetentionPolicy>> {
+    ensure_owner(conn, user, feed)?;
+    Ok(retention::read(conn, feed)?.map(|x| x.policy))
+}
+
+/// Makes a feed fall back to the global retention policy.
+pub fn unset(conn: &DbConnection, user: i32, feed: i32) -> Result<usize> {
+    ensure_owner(conn, user, feed)?;
+    retention::delete(conn, feed)
+}
+
+/// Applies the policy of each feed, or `global` for feeds without one, to the items of
+/// every user. Returns the number of deleted items.
+pub fn execute(conn: &DbConnection, global: &RetentionPolicy) -> Result<usize> {
+    let overrides = retention::read_all(conn)?;
+    let now = Utc::now().fixed_offset();
+
+    let mut deleted = 0;
+    for id in feed::read_all_ids(conn)? {
+        let policy = overrides
+            .iter()
+            .find(|x| x.feed == id)
+            .map_or(global, |x| &x.policy);
+        deleted += retention::purge(conn, id, policy, now)?;
+    }
+
+    Ok(deleted)
+}
+
+/// Keeps an item that never made it into a feed from being considered again.
+pub fn remember(conn: &DbConnection, arg: &PurgeToCreate) -> Result<usize> {
+    retention::create_purge(conn, arg, Utc::now().fixed_offset())
+}
+
+/// Tells which of the fingerprints of items offered by a feed were purged from it.
+pub fn purged_fingerprints(
+    conn: &DbConnection,
+    feed: i32,
+    fingerprints: &[String],
+) -> Result<HashSet<String>> {
+    retention::read_purged_fingerprints(conn, feed, fingerprints)
+}
+
+/// Forgets items purged longer ago than `duration`, which lets them be inserted again
+/// if they still appear in their feeds.
+pub fn forget(conn: &DbConnection, duration: Duration) -> Result<usize> {
+    retention::delete_purges_before(conn, Utc::now().fixed_offset() - duration)
+}
+
+fn ensure_owner(conn: &DbConnection, user: i32, id: i32) -> Result<()> {
+    match feed::read(conn, user, id)? {
+        Some(_) => Ok(()),
+        None => Err(Error::FeedNotFound),
+    }
+}
@@ -0,0 +1,222 @@
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::{
+    error::{Error, Result},
+    model::{
+        item::{Item, ItemReadOption, ItemStatus, ItemToCreate},
+        rule::{Rule, RuleAction, RuleField, RuleSubject, RuleToCreate, RuleToUpdate},
+    },
+    repository::{database::DbConnection, feed, item, rule},
+};
+
+const DRY_RUN_PAGE_SIZE: u64 = 200;
+
+pub fn create(conn: &DbConnection, user: i32, arg: &RuleToCreate) -> Result<i32> {
+    validate(conn, user, arg)?;
+    rule::create(conn, user, arg)
+}
+
+pub fn read_all(conn: &DbConnection, user: i32) -> Result<Vec<Rule>> {
+    rule::read_all(conn, user)
+}
+
+pub fn read(conn: &DbConnection, user: i32, id: i32) -> Result<Option<Rule>> {
+    rule::read(conn, user, id)
+}
+
+pub fn update(conn: &DbConnection, user: i32, arg: &RuleToUpdate) -> Result<usize> {
+    let Some(current) = rule::read(conn, user, arg.id)? else {
+        return Ok(0);
+    };
+
+    validate(
+        conn,
+        user,
+        &RuleToCreate {
+            feed: current.feed,
+            field: arg.field.unwrap_or(current.field),
+            pattern: arg.pattern.clone().unwrap_or(current.pattern),
+            is_regex: arg.is_regex.unwrap_or(current.is_regex),
+            action: arg.action.unwrap_or(current.action),
+            tag: arg.tag.clone().or(current.tag),
+        },
+    )?;
+
+    rule::update(conn, user, arg)
+}
+
+pub fn delete(conn: &DbConnection, user: i32, id: i32) -> Result<usize> {
+    rule::delete(conn, user, id)
+}
+
+/// Tests a rule against the existing items of the user without changing anything, and
+/// returns up to `limit` of the most recent items it would have matched.
+pub fn dry_run(
+    conn: &DbConnection,
+    user: i32,
+    arg: &RuleToCreate,
+    limit: usize,
+) -> Result<Vec<Item>> {
+    validate(conn, user, arg)?;
+    let pattern = Pattern::new(&arg.pattern, arg.is_regex)?;
+
+    // Items are read a page at a time, so that large histories aren't loaded at once.
+    let mut matched = vec![];
+    let mut after = None;
+    while matched.len() < limit {
+        let page = item::read_page(
+            conn,
+            user,
+            &ItemReadOption {
+                feed: arg.feed,
+                limit: Some(DRY_RUN_PAGE_SIZE),
+                after,
+                ..Default::default()
+            },
+        )?;
+
+        matched.extend(
+            page.items
+                .into_iter()
+                .filter(|x| pattern.is_match(RuleSubject::from(x).get(arg.field))),
+        );
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+
+    matched.truncate(limit);
+    Ok(matched)
+}
+
+/// Compiles the rules that apply to a feed. Rules that no longer compile are ignored
+/// rather than blocking ingestion.
+pub fn matchers(conn: &DbConnection, feed: i32) -> Result<Vec<Matcher>> {
+    Ok(rule::read_all_for_feed(conn, feed)?
+        .iter()
+        .filter_map(|x| Matcher::new(x).ok())
+        .collect())
+}
+
+pub fn record_matches(conn: &DbConnection, matches: &HashMap<i32, usize>) -> Result<()> {
+    for (id, count) in matches {
+        rule::increase_matches(conn, *id, *count)?;
+    }
+
+    Ok(())
+}
+
+/// Remembers the rules that matched a skipped item, returning those that hadn't matched
+/// it before. Skipped items are offered again on every poll, so that they are counted once.
+pub fn remember_skip(
+    conn: &DbConnection,
+    feed: i32,
+    fingerprint: &str,
+    matched: &[i32],
+) -> Result<Vec<i32>> {
+    let mut remembered = vec![];
+    for id in matched {
+        if rule::remember_skip(conn, *id, feed, fingerprint)? > 0 {
+            remembered.push(*id);
+        }
+    }
+
+    Ok(remembered)
+}
+
+/// What the matching rules decided about an item.
+#[derive(Debug, Default)]
+pub struct Decision {
+    pub skip: bool,
+    pub tags: Vec<String>,
+    pub matched: Vec<i32>,
+}
+
+/// Runs the matchers against an item about to be inserted, marking it read or saved
+/// in place as the rules say.
+pub fn apply(matchers: &[Matcher], arg: &mut ItemToCreate) -> Decision {
+    let mut decision = Decision::default();
+
+    for matcher in matchers {
+        if !matcher.is_match(&RuleSubject::from(&*arg)) {
+            continue;
+        }
+
+        decision.matched.push(matcher.id);
+        match matcher.action {
+            RuleAction::Skip => decision.skip = true,
+            RuleAction::MarkRead => arg.status = ItemStatus::Read,
+            RuleAction::Save => arg.is_saved = true,
+            RuleAction::Tag => decision.tags.extend(matcher.tag.clone()),
+        }
+    }
+
+    decision
+}
+
+pub struct Matcher {
+    pub id: i32,
+    pub field: RuleField,
+    pub action: RuleAction,
+    pub tag: Option<String>,
+    pattern: Pattern,
+}
+
+impl Matcher {
+    pub fn new(rule: &Rule) -> Result<Self> {
+        Ok(Self {
+            id: rule.id,
+            field: rule.field,
+            action: rule.action,
+            tag: rule.tag.clone(),
+            pattern: Pattern::new(&rule.pattern, rule.is_regex)?,
+        })
+    }
+
+    pub fn is_match(&self, subject: &RuleSubject) -> bool {
+        self.pattern.is_match(subject.get(self.field))
+    }
+}
+
+enum Pattern {
+    /// Matched case-insensitively anywhere in the field.
+    Keyword(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn new(pattern: &str, is_regex: bool) -> Result<Self> {
+        if is_regex {
+            Ok(Self::Regex(Regex::new(pattern)?))
+        } else {
+            Ok(Self::Keyword(pattern.to_lowercase()))
+        }
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Self::Keyword(keyword) => text.to_lowercase().contains(keyword),
+            Self::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+fn validate(conn: &DbConnection, user: i32, arg: &RuleToCreate) -> Result<()> {
+    if arg.pattern.is_empty() {
+        return Err(Error::BadArgument);
+    }
+
+    if arg.action == RuleAction::Tag && arg.tag.as_deref().unwrap_or_default().is_empty() {
+        return Err(Error::BadArgument);
+    }
+
+    if let Some(id) = arg.feed {
+        if feed::read(conn, user, id)?.is_none() {
+            return Err(Error::FeedNotFound);
+        }
+    }
+
+    Pattern::new(&arg.pattern, arg.is_regex).map(|_| ())
+}
@@ -0,0 +1,12 @@
+use crate::{
+    error::Result,
+    repository::{database::DbConnection, tag},
+};
+
+pub fn create(conn: &DbConnection, feed: i32, fingerprint: &str, name: &str) -> Result<usize> {
+    tag::create(conn, feed, fingerprint, name)
+}
+
+pub fn read_all(conn: &DbConnection, user: i32, item: i32) -> Result<Vec<String>> {
+    tag::read_all(conn, user, item)
+}
@@ -0,0 +1,27 @@
+use crate::{
+    error::Result,
+    model::user::{User, UserToCreate},
+    repository::{database::DbConnection, user},
+};
+
+pub fn create(conn: &DbConnection, arg: &UserToCreate) -> Result<i32> {
+    user::create(conn, arg)
+}
+
+pub fn read_all(conn: &DbConnection) -> Result<Vec<User>> {
+    user::read_all(conn)
+}
+
+pub fn read(conn: &DbConnection, id: i32) -> Result<Option<User>> {
+    user::read(conn, id)
+}
+
+pub fn delete(conn: &DbConnection, id: i32) -> Result<usize> {
+    user::delete(conn, id)
+}
+
+/// Hands feeds and items that predate multi-user support over to the given user.
+/// Returns the number of adopted feeds.
+pub fn adopt(conn: &DbConnection, id: i32) -> Result<usize> {
+    user::adopt(conn, id)
+}
@@ -0,0 +1,85 @@
+use rusqlite::Connection;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use crate::model::feed::FeedToCreate;
+use crate::model::user::UserToCreate;
+use crate::repository::database::{
+    feed_icons_table, feed_selectors_table, feeds_table, icons_table, items_backfill,
+    items_indexes, items_table, purges_table, read_history_indexes, read_history_table,
+    retentions_table, rule_skips_table, rules_table, tags_table, users_table, DbConnection,
+    Migration,
+};
+use crate::service::{feed, user};
+
+/// Returns the absolute path of a fixture, which stands for a link since the fetcher reads
+/// local files in tests.
+pub fn fixture(path: &str) -> String {
+    fs::canonicalize(PathBuf::from(format!("src/tests/fixtures/{}", path)))
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+pub fn migrate(db: &Connection) {
+    Migration::new()
+        .table(users_table())
+        .table(feeds_table())
+        .table(items_table())
+        .table(read_history_table())
+        .table(retentions_table())
+        .table(purges_table())
+        .table(rules_table())
+        .table(rule_skips_table())
+        .table(tags_table())
+        .table(icons_table())
+        .table(feed_selectors_table())
+        .table(feed_icons_table())
+        .backfill(items_backfill())
+        .index(items_indexes())
+        .index(read_history_indexes())
+        .migrate(db)
+        .unwrap();
+}
+
+pub fn connection() -> DbConnection {
+    let db = Connection::open_in_memory().unwrap();
+    migrate(&db);
+    Arc::new(Mutex::new(db))
+}
+
+pub fn user(conn: &DbConnection, name: &str) -> i32 {
+    user::create(
+        conn,
+        &UserToCreate {
+            name: name.to_string(),
+        },
+    )
+    .unwrap()
+}
+
+/// Creates alice with an empty custom feed, for tests that create items themselves.
+/// Returns the connection, alice and the feed.
+pub fn setup() -> (DbConnection, i32, i32) {
+    let conn = connection();
+    let alice = user(&conn, "alice");
+    feed::create_custom(
+        &conn,
+        alice,
+        &FeedToCreate {
+            title: "Notes".to_string(),
+            link: "notes".to_string(),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        "notes",
+    )
+    .unwrap();
+    let feed = feed::read_all(&conn, alice).unwrap()[0].id;
+
+    (conn, alice, feed)
+}
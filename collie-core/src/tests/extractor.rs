@@ -0,0 +1,106 @@
+use pretty_assertions::assert_eq;
+use std::fs;
+
+use super::common::{connection, fixture, user};
+use crate::model::feed::{FeedToCreate, FeedToUpdate};
+use crate::model::item::ItemReadOption;
+use crate::service::{feed, item};
+use crate::util::extractor;
+use crate::worker::Worker;
+
+#[test]
+fn extract_article() {
+    let html = fs::read_to_string(fixture("article.html")).unwrap();
+    let article = extractor::extract(&html, "https://engineering.example.com/").unwrap();
+
+    assert!(article.contains("started as a weekend project"));
+    assert!(article.contains("src=\"https://engineering.example.com/posts/images/memory.png\""));
+    assert!(!article.contains("newsletter"));
+    assert!(!article.contains("Great post"));
+
+    assert_eq!(None, extractor::extract("<p>Too short.</p>", ""));
+}
+
+#[tokio::test]
+async fn fetch_full_content() {
+    let conn = connection();
+
+    let alice = user(&conn, "alice");
+    feed::create(
+        &conn,
+        alice,
+        &FeedToCreate {
+            title: String::new(),
+            link: fixture("article-feed.rss"),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+    let id = feed::read_all(&conn, alice).unwrap()[0].id;
+    feed::update(
+        &conn,
+        alice,
+        &FeedToUpdate {
+            id,
+            title: None,
+            link: None,
+            status: None,
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: Some(true),
+            selectors: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        2,
+        Worker::new(conn.clone(), None)
+            .sanitize(None)
+            .execute()
+            .await
+            .unwrap()
+            .len()
+    );
+
+    let items = item::read_all(&conn, alice, &ItemReadOption::default()).unwrap();
+    let fetched = items
+        .iter()
+        .find(|x| x.link.ends_with("article.html"))
+        .unwrap();
+    let missing = items
+        .iter()
+        .find(|x| x.link.ends_with("missing.html"))
+        .unwrap();
+    assert_eq!(
+        "Our indexer started as a weekend project.",
+        fetched.description
+    );
+    assert!(fetched
+        .content
+        .as_deref()
+        .is_some_and(|x| x.contains("streaming design") && x.contains("<script>")));
+    assert_eq!(None, missing.content);
+
+    // Articles stored verbatim are still sanitized on read.
+    let items = item::read_all(
+        &conn,
+        alice,
+        &ItemReadOption {
+            sanitize: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let fetched = items
+        .iter()
+        .find(|x| x.link.ends_with("article.html"))
+        .unwrap();
+    assert!(fetched
+        .content
+        .as_deref()
+        .is_some_and(|x| x.contains("streaming design") && !x.contains("trackReader")));
+}
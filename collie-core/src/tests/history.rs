@@ -0,0 +1,180 @@
+use chrono::{Duration, Utc};
+use pretty_assertions::assert_eq;
+
+use super::common::{self, user};
+use crate::model::item::{ItemReadOption, ItemStatus, ItemToCreate, ItemToUpdate, ItemToUpdateAll};
+use crate::repository::database::DbConnection;
+use crate::service::{history, item};
+
+fn setup() -> (DbConnection, i32, i32) {
+    let (conn, alice, feed) = common::setup();
+    for (title, days) in [("a", 0), ("b", 1), ("c", 2)] {
+        item::create(
+            &conn,
+            &ItemToCreate {
+                author: None,
+                title: title.to_string(),
+                description: String::new(),
+                content: None,
+                link: format!("notes/{title}"),
+                source_link: None,
+                status: ItemStatus::Unread,
+                is_saved: false,
+                published_at: (Utc::now() - Duration::days(days)).fixed_offset(),
+                feed,
+            },
+        )
+        .unwrap();
+    }
+
+    (conn, alice, feed)
+}
+
+fn id(conn: &DbConnection, user: i32, title: &str) -> i32 {
+    item::read_all(conn, user, &ItemReadOption::default())
+        .unwrap()
+        .into_iter()
+        .find(|x| x.title == title)
+        .unwrap()
+        .id
+}
+
+fn mark(conn: &DbConnection, user: i32, title: &str, status: ItemStatus) {
+    item::update(
+        conn,
+        user,
+        &ItemToUpdate {
+            id: id(conn, user, title),
+            status: Some(status),
+            is_saved: None,
+        },
+    )
+    .unwrap();
+}
+
+fn mark_all_read(conn: &DbConnection, user: i32) -> String {
+    item::update_all_with_batch(
+        conn,
+        user,
+        &ItemToUpdateAll {
+            status: Some(ItemStatus::Read),
+            is_saved: None,
+            opt: None,
+        },
+    )
+    .unwrap()
+    .id
+}
+
+fn unread(conn: &DbConnection, user: i32) -> Vec<String> {
+    let mut titles = item::read_all(
+        conn,
+        user,
+        &ItemReadOption {
+            status: Some(ItemStatus::Unread),
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .into_iter()
+    .map(|x| x.title)
+    .collect::<Vec<_>>();
+    titles.sort();
+    titles
+}
+
+#[test]
+fn undo_bulk_updates() {
+    let (conn, alice, _) = setup();
+    mark(&conn, alice, "c", ItemStatus::Read);
+    let read_at = |title: &str| {
+        item::read_all(&conn, alice, &ItemReadOption::default())
+            .unwrap()
+            .into_iter()
+            .find(|x| x.title == title)
+            .unwrap()
+            .read_at
+    };
+    let c_read_at = read_at("c");
+
+    let batch = mark_all_read(&conn, alice);
+    assert!(unread(&conn, alice).is_empty());
+    let recent = history::recently_read(&conn, alice, 10).unwrap();
+    assert_eq!(3, recent.len());
+
+    // Someone else can't undo the batch.
+    let bob = user(&conn, "bob");
+    assert_eq!(0, history::undo(&conn, bob, &batch).unwrap());
+
+    // Items marked unread again since are left out, and those read before stay read.
+    mark(&conn, alice, "b", ItemStatus::Unread);
+    assert_eq!(1, history::undo(&conn, alice, &batch).unwrap());
+    assert_eq!(vec!["a", "b"], unread(&conn, alice));
+    assert_eq!(c_read_at, read_at("c"));
+    assert_eq!(None, read_at("a"));
+    assert_eq!(
+        vec!["c"],
+        history::recently_read(&conn, alice, 10)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.title)
+            .collect::<Vec<_>>()
+    );
+
+    // A batch can only be undone once.
+    assert_eq!(0, history::undo(&conn, alice, &batch).unwrap());
+}
+
+#[test]
+fn prune_history() {
+    let (conn, alice, _) = setup();
+    let batch = mark_all_read(&conn, alice);
+
+    assert_eq!(0, history::prune(&conn, Duration::days(1)).unwrap());
+    assert_eq!(3, history::prune(&conn, Duration::zero()).unwrap());
+    assert_eq!(0, history::undo(&conn, alice, &batch).unwrap());
+    assert!(unread(&conn, alice).is_empty());
+}
+
+#[test]
+fn update_without_history() {
+    let (conn, alice, _) = setup();
+    conn.lock()
+        .unwrap()
+        .execute("DROP TABLE read_history", [])
+        .unwrap();
+
+    let batch = item::update_all_with_batch(
+        &conn,
+        alice,
+        &ItemToUpdateAll {
+            status: Some(ItemStatus::Read),
+            is_saved: None,
+            opt: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(3, batch.count);
+    assert!(unread(&conn, alice).is_empty());
+    assert_eq!(0, history::undo(&conn, alice, &batch.id).unwrap());
+}
+
+#[test]
+fn history_is_indexed() {
+    let (conn, _, _) = setup();
+    let plan = |sql: &str| {
+        let db = conn.lock().unwrap();
+        let mut stmt = db.prepare(&format!("EXPLAIN QUERY PLAN {sql}")).unwrap();
+        let rows = stmt.query_map([], |row| row.get::<_, String>(3)).unwrap();
+        rows.map(std::result::Result::unwrap).collect::<String>()
+    };
+
+    assert!(
+        plan("SELECT * FROM read_history WHERE user = 1 AND batch = 'a'")
+            .contains("idx_read_history_user_batch")
+    );
+    assert!(
+        plan("DELETE FROM read_history WHERE changed_at < '2024-01-01'")
+            .contains("idx_read_history_changed_at")
+    );
+}
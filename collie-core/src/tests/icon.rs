@@ -0,0 +1,66 @@
+use chrono::Duration;
+use pretty_assertions::assert_eq;
+use std::fs;
+
+use super::common::{connection, fixture, user};
+use crate::model::feed::FeedToCreate;
+use crate::repository::database::DbConnection;
+use crate::service::{feed, icon};
+
+async fn subscribe(conn: &DbConnection, name: &str) -> (i32, i32) {
+    let user = user(conn, name);
+    feed::create(
+        conn,
+        user,
+        &FeedToCreate {
+            title: String::new(),
+            link: fixture("icon-feed.rss"),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+
+    (user, feed::read_all(conn, user).unwrap()[0].id)
+}
+
+#[tokio::test]
+async fn fetch_icon_from_site() {
+    let conn = connection();
+
+    let (alice, alice_feed) = subscribe(&conn, "alice").await;
+    let (bob, bob_feed) = subscribe(&conn, "bob").await;
+
+    assert_eq!(
+        2,
+        icon::refresh_all(&conn, Duration::days(7), None)
+            .await
+            .unwrap()
+    );
+    assert_eq!(
+        0,
+        icon::refresh_all(&conn, Duration::days(7), None)
+            .await
+            .unwrap()
+    );
+
+    let png = fs::read(fixture("icon.png")).unwrap();
+    assert_eq!(
+        Some(("image/png".to_string(), png.clone())),
+        icon::icon(&conn, alice, alice_feed).unwrap()
+    );
+    assert_eq!(
+        Some(("image/png".to_string(), png)),
+        icon::icon(&conn, bob, bob_feed).unwrap()
+    );
+    assert_eq!(None, icon::icon(&conn, bob, alice_feed).unwrap());
+
+    let icons: i64 = conn
+        .lock()
+        .unwrap()
+        .query_row("SELECT COUNT(*) FROM icons", [], |x| x.get(0))
+        .unwrap();
+    assert_eq!(1, icons);
+}
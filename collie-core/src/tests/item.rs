@@ -0,0 +1,704 @@
+use chrono::{DateTime, Duration, FixedOffset};
+use pretty_assertions::assert_eq;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::common::{migrate, setup, user};
+use crate::error::Error;
+use crate::model::feed::{FeedStatus, FeedToCreate, FeedToUpdate};
+use crate::model::item::{
+    DailyItemCounts, FeedItemCounts, ItemCounts, ItemOrder, ItemReadOption, ItemStatus,
+    ItemToCreate, ItemToUpdate, ItemToUpdateAll,
+};
+use crate::repository::database::DbConnection;
+use crate::service::{feed, item};
+
+fn create_feed(conn: &DbConnection, user: i32, link: &str) -> i32 {
+    feed::create_custom(
+        conn,
+        user,
+        &FeedToCreate {
+            title: String::new(),
+            link: link.to_string(),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        "notes",
+    )
+    .unwrap();
+
+    feed::read_all(conn, user)
+        .unwrap()
+        .into_iter()
+        .find(|x| x.link == link)
+        .unwrap()
+        .id
+}
+
+fn published_at(days: i64) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z").unwrap() + Duration::days(days)
+}
+
+fn create_item(conn: &DbConnection, feed: i32, title: &str, days: i64) {
+    create_item_by(conn, feed, title, days, None);
+}
+
+fn create_item_by(conn: &DbConnection, feed: i32, title: &str, days: i64, author: Option<&str>) {
+    item::create(
+        conn,
+        &ItemToCreate {
+            author: author.map(str::to_string),
+            title: title.to_string(),
+            description: String::new(),
+            content: None,
+            link: format!("notes/{title}"),
+            source_link: None,
+            status: ItemStatus::Unread,
+            is_saved: false,
+            published_at: published_at(days),
+            feed,
+        },
+    )
+    .unwrap();
+}
+
+fn titles(
+    conn: &DbConnection,
+    user: i32,
+    opt: &ItemReadOption,
+) -> (Vec<String>, Option<String>, Option<String>) {
+    let page = item::read_page(conn, user, opt).unwrap();
+    (
+        page.items.into_iter().map(|x| x.title).collect(),
+        page.prev,
+        page.next,
+    )
+}
+
+#[test]
+fn paginate_with_cursors() {
+    let (conn, alice, feed) = setup();
+    for (title, days) in [("a", 3), ("b", 1), ("c", 4), ("d", 1), ("e", 0)] {
+        create_item(&conn, feed, title, days);
+    }
+
+    let page = |after: Option<String>, before: Option<String>| ItemReadOption {
+        order_by: Some(ItemOrder::PublishedDateDesc),
+        limit: Some(2),
+        after,
+        before,
+        ..Default::default()
+    };
+
+    let (first, prev, next) = titles(&conn, alice, &page(None, None));
+    assert_eq!(vec!["c", "a"], first);
+    assert_eq!(None, prev);
+
+    // Items coming in while scrolling don't shift the following pages.
+    create_item(&conn, feed, "f", 5);
+
+    let (second, prev, next) = titles(&conn, alice, &page(next, None));
+    assert_eq!(vec!["d", "b"], second);
+    let (third, _, last) = titles(&conn, alice, &page(next, None));
+    assert_eq!(vec!["e"], third);
+    assert_eq!(None, last);
+
+    let (back, before_first, _) = titles(&conn, alice, &page(None, prev));
+    assert_eq!(vec!["c", "a"], back);
+    let (newest, none, _) = titles(&conn, alice, &page(None, before_first));
+    assert_eq!(vec!["f"], newest);
+    assert_eq!(None, none);
+
+    assert!(matches!(
+        item::read_page(&conn, alice, &page(Some("zz".to_string()), None)),
+        Err(Error::BadArgument)
+    ));
+}
+
+#[test]
+fn paginate_unread_first() {
+    let (conn, alice, feed) = setup();
+    for (title, days) in [("a", 3), ("b", 2), ("c", 1), ("d", 0)] {
+        create_item(&conn, feed, title, days);
+    }
+    let b = item::read_all(&conn, alice, &ItemReadOption::default())
+        .unwrap()
+        .into_iter()
+        .find(|x| x.title == "b")
+        .unwrap();
+    item::update(
+        &conn,
+        alice,
+        &ItemToUpdate {
+            id: b.id,
+            status: Some(ItemStatus::Read),
+            is_saved: None,
+        },
+    )
+    .unwrap();
+
+    let mut seen = vec![];
+    let mut after = None;
+    loop {
+        let (titles, _, next) = titles(
+            &conn,
+            alice,
+            &ItemReadOption {
+                order_by: Some(ItemOrder::UnreadFirst),
+                limit: Some(1),
+                after,
+                ..Default::default()
+            },
+        );
+        seen.extend(titles);
+        match next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(vec!["a", "c", "d", "b"], seen);
+}
+
+#[test]
+fn filter_items() {
+    let (conn, alice, notes) = setup();
+    let drafts = create_feed(&conn, alice, "drafts");
+    let archive = create_feed(&conn, alice, "archive");
+    create_item_by(&conn, notes, "a", 0, Some("Ada Lovelace"));
+    create_item_by(&conn, notes, "b", 1, Some("Grace Hopper,Ada"));
+    create_item_by(&conn, drafts, "c", 2, Some("100%_real"));
+    create_item_by(&conn, drafts, "d", 3, None);
+    create_item_by(&conn, archive, "e", 4, Some("ada"));
+    let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+    feed::update(
+        &conn,
+        alice,
+        &FeedToUpdate {
+            id: archive,
+            title: None,
+            link: None,
+            status: Some(FeedStatus::Unsubscribed),
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: None,
+            selectors: None,
+        },
+    )
+    .unwrap();
+
+    let cases = [
+        (
+            ItemReadOption {
+                feeds: vec![notes, archive],
+                ..Default::default()
+            },
+            vec!["a", "b", "e"],
+        ),
+        (
+            ItemReadOption {
+                exclude_feeds: vec![notes],
+                ..Default::default()
+            },
+            vec!["c", "d", "e"],
+        ),
+        (
+            ItemReadOption {
+                published_after: Some(published_at(1)),
+                published_before: Some(published_at(3)),
+                ..Default::default()
+            },
+            vec!["b", "c"],
+        ),
+        (
+            ItemReadOption {
+                published_after: Some(published_at(1).with_timezone(&tokyo)),
+                published_before: Some(published_at(3).with_timezone(&tokyo)),
+                ..Default::default()
+            },
+            vec!["b", "c"],
+        ),
+        (
+            ItemReadOption {
+                author: Some("ADA".to_string()),
+                ..Default::default()
+            },
+            vec!["a", "b", "e"],
+        ),
+        (
+            ItemReadOption {
+                author: Some("%_".to_string()),
+                ..Default::default()
+            },
+            vec!["c"],
+        ),
+        (
+            ItemReadOption {
+                author: Some("ada".to_string()),
+                feed_status: Some(FeedStatus::Subscribed),
+                ..Default::default()
+            },
+            vec!["a", "b"],
+        ),
+    ];
+
+    for (opt, expected) in cases {
+        let mut titles = item::read_all(&conn, alice, &opt)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.title)
+            .collect::<Vec<_>>();
+        titles.sort();
+        assert_eq!(expected, titles);
+        assert_eq!(
+            expected.len() as i64,
+            item::count_all(&conn, alice, &opt).unwrap()
+        );
+        assert_eq!(
+            expected.len(),
+            item::update_all(
+                &conn,
+                alice,
+                &ItemToUpdateAll {
+                    status: Some(ItemStatus::Unread),
+                    is_saved: None,
+                    opt: Some(opt),
+                },
+            )
+            .unwrap()
+        );
+    }
+}
+
+fn random_option(rng: &mut StdRng, feeds: &[i32], ids: &[i32]) -> ItemReadOption {
+    let pick = |rng: &mut StdRng, xs: &[i32]| {
+        xs.iter()
+            .copied()
+            .filter(|_| rng.gen_bool(0.4))
+            .collect::<Vec<_>>()
+    };
+
+    ItemReadOption {
+        ids: rng.gen_bool(0.2).then(|| pick(rng, ids)),
+        feed: rng
+            .gen_bool(0.2)
+            .then(|| feeds[rng.gen_range(0..feeds.len())]),
+        feeds: if rng.gen_bool(0.3) {
+            pick(rng, feeds)
+        } else {
+            vec![]
+        },
+        exclude_feeds: if rng.gen_bool(0.3) {
+            pick(rng, feeds)
+        } else {
+            vec![]
+        },
+        status: rng.gen_bool(0.3).then(|| {
+            if rng.gen_bool(0.5) {
+                ItemStatus::Read
+            } else {
+                ItemStatus::Unread
+            }
+        }),
+        is_saved: rng.gen_bool(0.3).then(|| rng.gen_bool(0.5)),
+        published_after: rng
+            .gen_bool(0.3)
+            .then(|| published_at(rng.gen_range(0..10))),
+        published_before: rng
+            .gen_bool(0.3)
+            .then(|| published_at(rng.gen_range(0..10))),
+        author: rng
+            .gen_bool(0.3)
+            .then(|| ["ada", "Hopper", "%", "x"][rng.gen_range(0..4)].to_string()),
+        feed_status: rng.gen_bool(0.3).then(|| {
+            if rng.gen_bool(0.5) {
+                FeedStatus::Subscribed
+            } else {
+                FeedStatus::Unsubscribed
+            }
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn queries_agree_on_filters() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let (conn, alice, notes) = setup();
+    let bob = user(&conn, "bob");
+
+    let feeds = vec![
+        notes,
+        create_feed(&conn, alice, "drafts"),
+        create_feed(&conn, alice, "archive"),
+        create_feed(&conn, bob, "notes"),
+    ];
+    feed::update(
+        &conn,
+        alice,
+        &FeedToUpdate {
+            id: feeds[2],
+            title: None,
+            link: None,
+            status: Some(FeedStatus::Unsubscribed),
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: None,
+            selectors: None,
+        },
+    )
+    .unwrap();
+
+    let authors = [
+        None,
+        Some("Ada Lovelace"),
+        Some("Grace Hopper,Ada"),
+        Some("50% off"),
+    ];
+    for i in 0..40 {
+        let feed = feeds[rng.gen_range(0..feeds.len())];
+        let author = authors[rng.gen_range(0..authors.len())];
+        create_item_by(
+            &conn,
+            feed,
+            &format!("item {i}"),
+            rng.gen_range(0..10),
+            author,
+        );
+    }
+    let ids = item::read_all(&conn, alice, &ItemReadOption::default())
+        .unwrap()
+        .into_iter()
+        .map(|x| x.id)
+        .collect::<Vec<_>>();
+
+    let state = |user: i32| {
+        let mut items = item::read_all(&conn, user, &ItemReadOption::default())
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.id, x.status, x.is_saved))
+            .collect::<Vec<_>>();
+        items.sort_by_key(|x| x.0);
+        items
+    };
+
+    for _ in 0..200 {
+        let opt = random_option(&mut rng, &feeds, &ids);
+        let matched = item::read_all(&conn, alice, &opt)
+            .unwrap()
+            .into_iter()
+            .map(|x| x.id)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            matched.len() as i64,
+            item::count_all(&conn, alice, &opt).unwrap(),
+            "{opt:?}"
+        );
+
+        // Flips the saved state of matching items, leaving every other item alone.
+        let is_saved = rng.gen_bool(0.5);
+        let before = state(alice);
+        let others = state(bob);
+        let updated = item::update_all(
+            &conn,
+            alice,
+            &ItemToUpdateAll {
+                status: None,
+                is_saved: Some(is_saved),
+                opt: Some(opt.clone()),
+            },
+        )
+        .unwrap();
+        assert_eq!(matched.len(), updated, "{opt:?}");
+
+        let expected = before
+            .into_iter()
+            .map(|(id, status, x)| (id, status, if matched.contains(&id) { is_saved } else { x }))
+            .collect::<Vec<_>>();
+        assert_eq!(expected, state(alice), "{opt:?}");
+        assert_eq!(others, state(bob));
+
+        // Moves some items between read and unread for the next round.
+        let id = ids[rng.gen_range(0..ids.len())];
+        item::update(
+            &conn,
+            alice,
+            &ItemToUpdate {
+                id,
+                status: Some(if rng.gen_bool(0.5) {
+                    ItemStatus::Read
+                } else {
+                    ItemStatus::Unread
+                }),
+                is_saved: None,
+            },
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn count_items_by_feed_and_day() {
+    let (conn, alice, notes) = setup();
+    let drafts = create_feed(&conn, alice, "drafts");
+    let empty = create_feed(&conn, alice, "empty");
+    create_item(&conn, notes, "a", 0);
+    create_item(&conn, notes, "b", 0);
+    create_item(&conn, notes, "c", 2);
+    create_item(&conn, drafts, "d", 2);
+
+    let items = item::read_all(&conn, alice, &ItemReadOption::default()).unwrap();
+    for x in items.iter().filter(|x| x.title == "a" || x.title == "d") {
+        item::update(
+            &conn,
+            alice,
+            &ItemToUpdate {
+                id: x.id,
+                status: Some(ItemStatus::Read),
+                is_saved: Some(true),
+            },
+        )
+        .unwrap();
+    }
+
+    let counts = |unread, saved, total| ItemCounts {
+        unread,
+        saved,
+        total,
+    };
+    assert_eq!(
+        vec![
+            FeedItemCounts {
+                feed: notes,
+                counts: counts(2, 1, 3),
+            },
+            FeedItemCounts {
+                feed: drafts,
+                counts: counts(0, 1, 1),
+            },
+            FeedItemCounts {
+                feed: empty,
+                counts: counts(0, 0, 0),
+            },
+        ],
+        item::counts_by_feed(&conn, alice).unwrap()
+    );
+
+    assert_eq!(
+        vec![
+            DailyItemCounts {
+                day: published_at(0).date_naive(),
+                counts: counts(1, 1, 2),
+            },
+            DailyItemCounts {
+                day: published_at(2).date_naive(),
+                counts: counts(1, 0, 1),
+            },
+        ],
+        item::counts_by_day(
+            &conn,
+            alice,
+            &ItemReadOption {
+                feed: Some(notes),
+                ..Default::default()
+            }
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn record_read_and_saved_times() {
+    let (conn, alice, feed) = setup();
+    for (title, days) in [("a", 0), ("b", 1), ("c", 2), ("d", 3)] {
+        create_item(&conn, feed, title, days);
+    }
+    let find = |title: &str| {
+        item::read_all(&conn, alice, &ItemReadOption::default())
+            .unwrap()
+            .into_iter()
+            .find(|x| x.title == title)
+            .unwrap()
+    };
+    let mark = |title: &str, status: Option<ItemStatus>, is_saved: Option<bool>| {
+        item::update(
+            &conn,
+            alice,
+            &ItemToUpdate {
+                id: find(title).id,
+                status,
+                is_saved,
+            },
+        )
+        .unwrap();
+    };
+    let order = |order_by: ItemOrder| {
+        item::read_all(
+            &conn,
+            alice,
+            &ItemReadOption {
+                order_by: Some(order_by),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .into_iter()
+        .map(|x| x.title)
+        .collect::<Vec<_>>()
+    };
+
+    // Items marked in a row may get the same time, so they're moved apart afterwards.
+    let backdate = |title: &str, column: &str, days: i64| {
+        conn.lock()
+            .unwrap()
+            .execute(
+                &format!("UPDATE items SET {column} = ?1 WHERE title = ?2"),
+                (published_at(days), title),
+            )
+            .unwrap();
+    };
+
+    mark("c", Some(ItemStatus::Read), None);
+    mark("a", Some(ItemStatus::Read), Some(true));
+    mark("b", None, Some(true));
+    // Marking again keeps the time it was first marked.
+    let read_at = find("c").read_at;
+    assert!(read_at.is_some());
+    mark("c", Some(ItemStatus::Read), None);
+    assert_eq!(read_at, find("c").read_at);
+
+    backdate("c", "read_at", 1);
+    backdate("a", "read_at", 2);
+    backdate("a", "saved_at", 1);
+    backdate("b", "saved_at", 2);
+
+    assert_eq!(vec!["d", "c", "b", "a"], order(ItemOrder::ReceivedDateDesc));
+    assert_eq!(vec!["a", "c", "d", "b"], order(ItemOrder::ReadDateDesc));
+    assert_eq!(vec!["b", "a", "d", "c"], order(ItemOrder::SavedDateDesc));
+
+    item::update_all(
+        &conn,
+        alice,
+        &ItemToUpdateAll {
+            status: Some(ItemStatus::Unread),
+            is_saved: None,
+            opt: None,
+        },
+    )
+    .unwrap();
+    let items = item::read_all(&conn, alice, &ItemReadOption::default()).unwrap();
+    assert!(items.iter().all(|x| x.read_at.is_none()));
+    assert_eq!(2, items.iter().filter(|x| x.saved_at.is_some()).count());
+
+    // Pages of recently saved items go on through the unsaved ones.
+    let mut seen = vec![];
+    let mut after = None;
+    loop {
+        let page = item::read_page(
+            &conn,
+            alice,
+            &ItemReadOption {
+                order_by: Some(ItemOrder::SavedDateDesc),
+                limit: Some(1),
+                after,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        seen.extend(page.items.into_iter().map(|x| x.title));
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(vec!["b", "a", "d", "c"], seen);
+}
+
+#[test]
+fn take_legacy_items_as_received_when_published() {
+    let (conn, alice, feed) = setup();
+    create_item(&conn, feed, "a", 1);
+    create_item(&conn, feed, "b", 0);
+    {
+        let db = conn.lock().unwrap();
+        db.execute("UPDATE items SET received_at = NULL WHERE title = 'a'", [])
+            .unwrap();
+        migrate(&db);
+    }
+
+    let items = item::read_all(
+        &conn,
+        alice,
+        &ItemReadOption {
+            order_by: Some(ItemOrder::ReceivedDateDesc),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!("b", items[0].title);
+    assert_eq!(Some(published_at(1)), items[1].received_at);
+}
+
+#[test]
+fn page_through_items_not_backfilled() {
+    let (conn, alice, feed) = setup();
+    create_item(&conn, feed, "a", 1);
+    create_item(&conn, feed, "b", 0);
+    create_item(&conn, feed, "c", 2);
+    conn.lock()
+        .unwrap()
+        .execute("UPDATE items SET received_at = NULL WHERE title != 'b'", [])
+        .unwrap();
+
+    let mut seen = vec![];
+    let mut after = None;
+    loop {
+        let page = item::read_page(
+            &conn,
+            alice,
+            &ItemReadOption {
+                limit: Some(1),
+                after,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        seen.extend(
+            page.items
+                .into_iter()
+                .map(|x| (x.title, x.received_at.is_some())),
+        );
+        match page.next {
+            Some(next) => after = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(
+        vec![
+            ("b".to_string(), true),
+            ("c".to_string(), false),
+            ("a".to_string(), false)
+        ],
+        seen
+    );
+}
+
+#[test]
+fn sort_by_indexed_times() {
+    let (conn, _, _) = setup();
+    let plan = |sql: &str| {
+        let db = conn.lock().unwrap();
+        let mut stmt = db.prepare(&format!("EXPLAIN QUERY PLAN {sql}")).unwrap();
+        let rows = stmt.query_map([], |row| row.get::<_, String>(3)).unwrap();
+        rows.map(std::result::Result::unwrap).collect::<Vec<_>>()
+    };
+
+    for column in ["received_at", "read_at", "saved_at"] {
+        let plan = plan(&format!(
+            "SELECT * FROM items INNER JOIN feeds ON items.feed = feeds.id \
+             WHERE items.user = 1 ORDER BY items.{column} DESC, items.id DESC LIMIT 10"
+        ));
+        assert!(plan
+            .iter()
+            .any(|x| x.contains(&format!("idx_items_user_{column}"))));
+        assert!(plan.iter().all(|x| !x.contains("TEMP B-TREE")), "{plan:?}");
+    }
+}
@@ -0,0 +1,103 @@
+use pretty_assertions::assert_eq;
+use std::{fs, path::PathBuf};
+
+use crate::model::feed::{FeedCandidate, FeedFormat};
+use crate::util::resolver::Registry;
+
+fn fixture(path: &str) -> String {
+    fs::read_to_string(PathBuf::from(format!("src/tests/fixtures/{}", path))).unwrap()
+}
+
+fn resolve(link: &str, html: &str) -> Option<(String, FeedFormat)> {
+    Registry::default()
+        .resolve(link, html)
+        .map(|FeedCandidate { link, format, .. }| (link, format))
+}
+
+#[test]
+fn resolve_youtube() {
+    let html = fixture("youtube-channel.html");
+    let channel = "https://www.youtube.com/feeds/videos.xml?channel_id=UCaYhcUwRBNscFNUKTjgPFiA";
+
+    assert_eq!(
+        Some((channel.to_string(), FeedFormat::Atom)),
+        resolve("https://www.youtube.com/@RustVideos", &html)
+    );
+    assert_eq!(
+        Some((channel.to_string(), FeedFormat::Atom)),
+        resolve(
+            "https://youtube.com/channel/UCaYhcUwRBNscFNUKTjgPFiA/videos",
+            ""
+        )
+    );
+    assert_eq!(
+        Some((
+            "https://www.youtube.com/feeds/videos.xml?playlist_id=PLZaoyhMXgBzoM9bfb5pyUOT3zjnaDdSEP"
+                .to_string(),
+            FeedFormat::Atom
+        )),
+        resolve(
+            "https://www.youtube.com/playlist?list=PLZaoyhMXgBzoM9bfb5pyUOT3zjnaDdSEP",
+            ""
+        )
+    );
+    assert_eq!(
+        Some((
+            "https://www.youtube.com/feeds/videos.xml?playlist_id=PL%26x%3D1".to_string(),
+            FeedFormat::Atom
+        )),
+        resolve("https://www.youtube.com/playlist?list=PL%26x%3D1", "")
+    );
+    assert_eq!(None, resolve("https://www.youtube.com/@RustVideos", ""));
+}
+
+#[test]
+fn resolve_reddit() {
+    let html = fixture("reddit-subreddit.html");
+    assert_eq!(
+        Some((
+            "https://www.reddit.com/r/rust.rss".to_string(),
+            FeedFormat::Atom
+        )),
+        resolve("https://old.reddit.com/r/rust/", &html)
+    );
+    assert_eq!(
+        Some((
+            "https://www.reddit.com/r/rust/new.rss".to_string(),
+            FeedFormat::Atom
+        )),
+        resolve("https://www.reddit.com/r/rust/new/", &html)
+    );
+}
+
+#[test]
+fn resolve_github() {
+    let html = fixture("github-repository.html");
+    assert_eq!(
+        Some((
+            "https://github.com/collie-reader/collie/releases.atom".to_string(),
+            FeedFormat::Atom
+        )),
+        resolve("https://github.com/collie-reader/collie", &html)
+    );
+    assert_eq!(None, resolve("https://github.com/trending/rust", &html));
+}
+
+#[test]
+fn resolve_mastodon() {
+    let html = fixture("mastodon-profile.html");
+    assert_eq!(
+        Some((
+            "https://hachyderm.io/@rust.rss".to_string(),
+            FeedFormat::Rss
+        )),
+        resolve("https://hachyderm.io/@rust", &html)
+    );
+    assert_eq!(
+        None,
+        resolve(
+            "https://medium.com/@rust",
+            &fixture("github-repository.html")
+        )
+    );
+}
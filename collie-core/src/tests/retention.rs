@@ -0,0 +1,120 @@
+use chrono::Duration;
+use pretty_assertions::assert_eq;
+
+use super::common::{connection, fixture, user};
+use crate::model::feed::FeedToCreate;
+use crate::model::item::{ItemReadOption, ItemStatus, ItemToUpdateAll};
+use crate::model::retention::RetentionPolicy;
+use crate::service::{feed, item, retention};
+use crate::worker::Worker;
+
+#[tokio::test]
+async fn purged_items_are_not_fetched_again() {
+    let conn = connection();
+
+    let alice = user(&conn, "alice");
+    feed::create(
+        &conn,
+        alice,
+        &FeedToCreate {
+            title: String::new(),
+            link: fixture("hnrss-org-frontpage.rss"),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+    let id = feed::read_all(&conn, alice).unwrap()[0].id;
+
+    let worker = Worker::new(conn.clone(), None).retention(RetentionPolicy {
+        max_count: Some(1),
+        ..Default::default()
+    });
+    assert_eq!(3, worker.execute().await.unwrap().len());
+
+    // Unread items are kept unless the policy says otherwise.
+    assert_eq!(0, worker.cleanup().unwrap());
+
+    item::update_all(
+        &conn,
+        alice,
+        &ItemToUpdateAll {
+            status: Some(ItemStatus::Read),
+            is_saved: None,
+            opt: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(2, worker.cleanup().unwrap());
+    assert_eq!(0, worker.execute().await.unwrap().len());
+    assert_eq!(
+        1,
+        item::count_all(&conn, alice, &ItemReadOption::default()).unwrap()
+    );
+
+    // A feed's own policy takes precedence over the global one.
+    retention::set(
+        &conn,
+        alice,
+        id,
+        &RetentionPolicy {
+            max_count: Some(0),
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(1, worker.cleanup().unwrap());
+    assert!(retention::set(&conn, alice + 1, id, &RetentionPolicy::default()).is_err());
+
+    // Items purged from a feed can still come from another one.
+    feed::create(
+        &conn,
+        alice,
+        &FeedToCreate {
+            title: String::new(),
+            link: fixture("hnrss-org-frontpage.atom"),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(3, worker.execute().await.unwrap().len());
+}
+
+#[tokio::test]
+async fn cleanup_forgets_old_purges() {
+    let conn = connection();
+    let alice = user(&conn, "alice");
+    feed::create(
+        &conn,
+        alice,
+        &FeedToCreate {
+            title: String::new(),
+            link: fixture("hnrss-org-frontpage.rss"),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+
+    let worker = Worker::new(conn.clone(), None)
+        .retention(RetentionPolicy {
+            max_count: Some(1),
+            delete_unread: true,
+            ..Default::default()
+        })
+        .purge_window(Duration::zero());
+    assert_eq!(3, worker.execute().await.unwrap().len());
+    assert_eq!(2, worker.cleanup().unwrap());
+    assert_eq!(0, worker.execute().await.unwrap().len());
+
+    // The purges are forgotten by the next cleanup, so the items come back.
+    assert_eq!(0, worker.cleanup().unwrap());
+    assert_eq!(2, worker.execute().await.unwrap().len());
+}
@@ -0,0 +1,134 @@
+use pretty_assertions::assert_eq;
+
+use super::common::{connection, fixture, user};
+use crate::model::feed::FeedToCreate;
+use crate::model::item::{ItemReadOption, ItemStatus};
+use crate::model::rule::{RuleAction, RuleField, RuleToCreate, RuleToUpdate};
+use crate::service::{feed, item, rule, tag};
+use crate::worker::Worker;
+
+fn rule(field: RuleField, pattern: &str, is_regex: bool, action: RuleAction) -> RuleToCreate {
+    RuleToCreate {
+        feed: None,
+        field,
+        pattern: pattern.to_string(),
+        is_regex,
+        action,
+        tag: None,
+    }
+}
+
+#[tokio::test]
+async fn apply_rules_at_ingest() {
+    let conn = connection();
+
+    let alice = user(&conn, "alice");
+    feed::create(
+        &conn,
+        alice,
+        &FeedToCreate {
+            title: String::new(),
+            link: fixture("hnrss-org-frontpage.rss"),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+    let id = feed::read_all(&conn, alice).unwrap()[0].id;
+
+    let skip = rule::create(
+        &conn,
+        alice,
+        &rule(RuleField::Title, "HACKER NEWS", false, RuleAction::Skip),
+    )
+    .unwrap();
+    let mark_read = rule::create(
+        &conn,
+        alice,
+        &RuleToCreate {
+            feed: Some(id),
+            ..rule(RuleField::Title, r"\bAda\b", true, RuleAction::MarkRead)
+        },
+    )
+    .unwrap();
+    let tagging = RuleToCreate {
+        tag: Some("science".to_string()),
+        ..rule(
+            RuleField::Link,
+            r"^https://undark\.org/",
+            true,
+            RuleAction::Tag,
+        )
+    };
+    let tag_science = rule::create(&conn, alice, &tagging).unwrap();
+
+    assert!(rule::create(
+        &conn,
+        alice,
+        &rule(RuleField::Title, "(", true, RuleAction::Skip)
+    )
+    .is_err());
+    assert!(rule::create(
+        &conn,
+        alice,
+        &rule(RuleField::Title, "x", false, RuleAction::Tag)
+    )
+    .is_err());
+
+    let report = Worker::new(conn.clone(), None)
+        .execute_with_report()
+        .await
+        .unwrap();
+    assert_eq!(2, report.inserted.len());
+    assert_eq!(3, report.matches.len());
+    assert_eq!(
+        0,
+        Worker::new(conn.clone(), None)
+            .execute()
+            .await
+            .unwrap()
+            .len()
+    );
+
+    let items = item::read_all(&conn, alice, &ItemReadOption::default()).unwrap();
+    let ada = items.iter().find(|x| x.title.contains("Ada")).unwrap();
+    assert!(matches!(ada.status, ItemStatus::Read));
+    let undark = items.iter().find(|x| x.link.contains("undark")).unwrap();
+    assert_eq!(
+        vec!["science"],
+        tag::read_all(&conn, alice, undark.id).unwrap()
+    );
+
+    // Skipped items are counted once, not on every fetch.
+    for id in [skip, mark_read, tag_science] {
+        assert_eq!(1, rule::read(&conn, alice, id).unwrap().unwrap().matches);
+    }
+
+    assert_eq!(1, rule::dry_run(&conn, alice, &tagging, 10).unwrap().len());
+    let any_link = rule(RuleField::Link, "http", false, RuleAction::MarkRead);
+    assert_eq!(2, rule::dry_run(&conn, alice, &any_link, 2).unwrap().len());
+
+    // Updates without any change leave the rule alone.
+    let unchanged = RuleToUpdate {
+        id: tag_science,
+        field: None,
+        pattern: None,
+        is_regex: None,
+        action: None,
+        tag: None,
+    };
+    assert_eq!(0, rule::update(&conn, alice, &unchanged).unwrap());
+
+    // Skips last as long as the rule.
+    rule::delete(&conn, alice, skip).unwrap();
+    assert_eq!(
+        1,
+        Worker::new(conn.clone(), None)
+            .execute()
+            .await
+            .unwrap()
+            .len()
+    );
+}
@@ -0,0 +1,122 @@
+use pretty_assertions::assert_eq;
+use sha1_smol::Sha1;
+
+use super::common::{connection, fixture, user};
+use crate::model::feed::FeedToCreate;
+use crate::model::item::ItemReadOption;
+use crate::service::{feed, item};
+use crate::util::sanitizer::{sanitize, strip_tracking_params, SanitizePolicy};
+use crate::worker::Worker;
+
+#[test]
+fn sanitize_description() {
+    let policy = SanitizePolicy::default();
+
+    assert_eq!(
+        "<p>Hello world</p>",
+        sanitize(
+            "<p onclick=\"steal()\">Hello <font>world</font></p><script>alert(1)</script>",
+            &policy
+        )
+    );
+    assert_eq!(
+        "<a rel=\"noopener noreferrer\">click</a>",
+        sanitize("<a href=\" java\tscript:alert(1)\">click</a>", &policy)
+    );
+    assert_eq!(
+        "<img alt=\"1 &lt; 2\" src=\"https://example.com/a.png?size=2\">",
+        sanitize(
+            "<img src=\"https://example.com/a.png?utm_source=rss&size=2\" alt=\"1 < 2\">\
+             <img src=\"https://tracker.example.com/p.gif\" width=\"1\" height=\"1\">\
+             <iframe src=\"https://ads.example.com\"></iframe>",
+            &policy
+        )
+    );
+}
+
+#[test]
+fn sanitize_with_custom_policy() {
+    let mut policy = SanitizePolicy::default();
+    policy.tags.insert("input".to_string());
+    policy
+        .attributes
+        .get_mut("a")
+        .unwrap()
+        .insert("rel".to_string());
+
+    assert_eq!(
+        "<p><input>done</p>",
+        sanitize("<p><input>done</p>", &policy)
+    );
+    assert_eq!(
+        "<a href=\"https://example.com\" rel=\"nofollow noopener noreferrer\">link</a>",
+        sanitize(
+            "<a rel=\"nofollow noopener\" href=\"https://example.com\">link</a>",
+            &policy
+        )
+    );
+}
+
+#[test]
+fn sanitize_deeply_nested_markup() {
+    let depth = 5_000;
+    let html = format!("{}deep{}", "<div>".repeat(depth), "</div>".repeat(depth));
+
+    let sanitized = sanitize(&html, &SanitizePolicy::default());
+    assert!(sanitized.contains("deep"));
+}
+
+#[test]
+fn strip_tracking_params_from_link() {
+    assert_eq!(
+        "https://example.com/post?id=1#comments",
+        strip_tracking_params("https://example.com/post?utm_medium=feed&id=1&fbclid=x#comments")
+    );
+    assert_eq!(
+        "https://example.com/post",
+        strip_tracking_params("https://example.com/post?utm_source=rss")
+    );
+}
+
+#[tokio::test]
+async fn stripping_tracking_params_keeps_fingerprints() {
+    let conn = connection();
+
+    let alice = user(&conn, "alice");
+    feed::create(
+        &conn,
+        alice,
+        &FeedToCreate {
+            title: String::new(),
+            link: fixture("tracking-items.rss"),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        1,
+        Worker::new(conn.clone(), None)
+            .execute()
+            .await
+            .unwrap()
+            .len()
+    );
+    let items = item::read_all(&conn, alice, &ItemReadOption::default()).unwrap();
+    assert_eq!("https://example.com/post?id=1", items[0].link);
+    assert_eq!(
+        Sha1::from("Tracked post:https://example.com/post?utm_source=rss&id=1").hexdigest(),
+        items[0].fingerprint
+    );
+
+    // Items stored before links were cleaned are known by the same fingerprint.
+    assert!(Worker::new(conn.clone(), None)
+        .sanitize(None)
+        .execute()
+        .await
+        .unwrap()
+        .is_empty());
+}
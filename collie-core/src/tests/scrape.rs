@@ -0,0 +1,176 @@
+use chrono::DateTime;
+use pretty_assertions::assert_eq;
+use std::{env, fs};
+
+use super::common::{connection, fixture, user};
+use crate::error::Error;
+use crate::model::feed::{FeedKind, FeedToCreate, FeedToUpdate, ScrapeSelectors};
+use crate::model::item::ItemReadOption;
+use crate::model::syndication::RawItem;
+use crate::service::{feed, item};
+use crate::worker::Worker;
+
+fn selectors() -> ScrapeSelectors {
+    ScrapeSelectors {
+        container: "li.news-item".to_string(),
+        title: "h2".to_string(),
+        link: "h2 a".to_string(),
+        date: Some("time".to_string()),
+        content: Some(".summary".to_string()),
+    }
+}
+
+#[test]
+fn scrape_items() {
+    let html = fs::read_to_string(fixture("scraped-news.html")).unwrap();
+    let items = item::scrape("https://council.example.org/news/", &html, &selectors()).unwrap();
+
+    assert_eq!(
+        vec![
+            RawItem {
+                title: "Road works on Main Street".to_string(),
+                author: None,
+                link: Some("https://council.example.org/news/road-works".to_string()),
+                source_link: None,
+                content: Some(
+                    "<p>Main Street is closed <a href=\"https://council.example.org/maps/main-street\">between the bridges</a>.</p>"
+                        .to_string()
+                ),
+                published_at: DateTime::parse_from_rfc3339("2024-03-04T08:00:00+01:00").ok(),
+            },
+            RawItem {
+                title: "New library hours".to_string(),
+                author: None,
+                link: Some("https://council.example.org/news/library-hours".to_string()),
+                source_link: None,
+                content: Some("<p>The library opens an hour earlier from April.</p>".to_string()),
+                published_at: DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z").ok(),
+            },
+            RawItem {
+                title: "Spring market".to_string(),
+                author: None,
+                link: Some("https://events.example.org/market".to_string()),
+                source_link: None,
+                content: Some("<p>Stalls are still available.</p>".to_string()),
+                published_at: None,
+            },
+        ],
+        items
+    );
+
+    let invalid = ScrapeSelectors {
+        title: "h2[".to_string(),
+        ..selectors()
+    };
+    assert!(matches!(
+        item::scrape("", &html, &invalid),
+        Err(Error::InvalidSelector(x)) if x == "h2["
+    ));
+}
+
+#[tokio::test]
+async fn scraped_feed() {
+    let conn = connection();
+
+    let alice = user(&conn, "alice");
+
+    // Pages are served from a copy so that the test can post news.
+    let page = env::temp_dir().join(format!("collie-scraped-news-{}.html", std::process::id()));
+    let html = fs::read_to_string(fixture("scraped-news.html")).unwrap();
+    let undated = html.replace("<time>2024-03-01</time>", "");
+    fs::write(&page, &undated).unwrap();
+    let link = page.to_str().unwrap().to_string();
+
+    let nothing = ScrapeSelectors {
+        container: "article".to_string(),
+        ..selectors()
+    };
+    assert!(matches!(
+        feed::create(
+            &conn,
+            alice,
+            &FeedToCreate {
+                title: String::new(),
+                link: link.clone(),
+                fetch_old_items: false,
+                selectors: Some(nothing),
+            },
+            None,
+        )
+        .await,
+        Err(Error::FeedNotFound)
+    ));
+
+    let undated_selectors = ScrapeSelectors {
+        date: None,
+        ..selectors()
+    };
+    feed::create(
+        &conn,
+        alice,
+        &FeedToCreate {
+            title: String::new(),
+            link: link.clone(),
+            fetch_old_items: false,
+            selectors: Some(undated_selectors.clone()),
+        },
+        None,
+    )
+    .await
+    .unwrap();
+
+    let feeds = feed::read_all(&conn, alice).unwrap();
+    assert_eq!("Town Council News", feeds[0].title);
+    assert_eq!(FeedKind::Scraped, feeds[0].kind);
+    assert_eq!(
+        Some(undated_selectors),
+        feed::read_selectors(&conn, feeds[0].id).unwrap()
+    );
+
+    // Only the top of the page is taken at first, and the rest isn't taken later.
+    let worker = Worker::new(conn.clone(), None);
+    let inserted = worker.execute().await.unwrap();
+    assert_eq!(1, inserted.len());
+    assert_eq!("Road works on Main Street", inserted[0].title);
+    assert_eq!(0, worker.execute().await.unwrap().len());
+
+    fs::write(
+        &page,
+        undated.replace(
+            "<ul class=\"news\">",
+            "<ul class=\"news\"><li class=\"news-item\"><h2><a href=\"/news/bins\">Bin collection moves to Friday</a></h2></li>",
+        ),
+    )
+    .unwrap();
+    let inserted = worker.execute().await.unwrap();
+    assert_eq!(1, inserted.len());
+    assert_eq!("Bin collection moves to Friday", inserted[0].title);
+
+    feed::update(
+        &conn,
+        alice,
+        &FeedToUpdate {
+            id: feeds[0].id,
+            title: None,
+            link: None,
+            status: None,
+            checked_at: None,
+            fetch_old_items: None,
+            fetch_full_content: None,
+            selectors: Some(selectors()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        Some(selectors()),
+        feed::read_selectors(&conn, feeds[0].id).unwrap()
+    );
+    assert_eq!(
+        2,
+        item::read_all(&conn, alice, &ItemReadOption::default())
+            .unwrap()
+            .len()
+    );
+
+    fs::remove_file(page).unwrap();
+}
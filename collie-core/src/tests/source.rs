@@ -0,0 +1,78 @@
+use async_trait::async_trait;
+use pretty_assertions::assert_eq;
+use std::fs;
+
+use super::common::{connection, fixture, user};
+use crate::error::{Error, Result};
+use crate::model::feed::{Feed, FeedKind, FeedToCreate};
+use crate::model::syndication::RawItem;
+use crate::service::feed;
+use crate::util::source::Source;
+use crate::worker::Worker;
+
+/// Reads Markdown files in the directory at the feed link, titled by their first line.
+struct Notes;
+
+#[async_trait]
+impl Source for Notes {
+    async fn fetch(&self, feed: &Feed) -> Result<Vec<RawItem>> {
+        let mut items = vec![];
+        for entry in fs::read_dir(&feed.link)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|x| x == "md") {
+                let text = fs::read_to_string(&path)?;
+                let (title, body) = text.split_once('\n').unwrap_or((&text, ""));
+                items.push(RawItem {
+                    title: title.trim_start_matches('#').trim().to_string(),
+                    author: None,
+                    link: path.to_str().map(str::to_string),
+                    source_link: None,
+                    content: Some(body.trim().to_string()),
+                    published_at: None,
+                });
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+fn feed_to_create(link: &str) -> FeedToCreate {
+    FeedToCreate {
+        title: String::new(),
+        link: link.to_string(),
+        fetch_old_items: true,
+        selectors: None,
+    }
+}
+
+#[tokio::test]
+async fn custom_source() {
+    let conn = connection();
+
+    let alice = user(&conn, "alice");
+
+    let notes = fixture("notes");
+    assert!(matches!(
+        feed::create_custom(&conn, alice, &feed_to_create(&notes), "syndication"),
+        Err(Error::BadArgument)
+    ));
+    feed::create_custom(&conn, alice, &feed_to_create(&notes), "notes").unwrap();
+    feed::create_custom(&conn, alice, &feed_to_create("inbox.mbox"), "mailbox").unwrap();
+
+    let feeds = feed::read_all(&conn, alice).unwrap();
+    assert_eq!(FeedKind::Custom("notes".to_string()), feeds[0].kind);
+    assert_eq!(notes, feeds[0].title);
+
+    // Feeds of kinds without a source are skipped.
+    let worker = Worker::new(conn.clone(), None).source("notes", Notes);
+    let mut titles = worker
+        .execute()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|x| x.title)
+        .collect::<Vec<_>>();
+    titles.sort();
+    assert_eq!(vec!["Moving the build to CI", "Release checklist"], titles);
+}
@@ -1,17 +1,12 @@
 use chrono::DateTime;
 use pretty_assertions::assert_eq;
-use std::{fs, path::PathBuf};
+use std::{env, fs};
 
-use crate::model::syndication::RawItem;
+use super::common::{connection, fixture, user};
+use crate::model::feed::{FeedCandidate, FeedFormat, FeedMetadata, FeedToCreate};
+use crate::model::syndication::{Feed as SyndicationFeed, RawItem};
 use crate::service::{feed, item};
-
-fn fixture(path: &str) -> String {
-    fs::canonicalize(PathBuf::from(format!("src/tests/fixtures/{}", path)))
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .to_string()
-}
+use crate::worker::Worker;
 
 #[tokio::test]
 async fn fetch_feed_title_rss() {
@@ -40,6 +35,7 @@ async fn fetch_feed_items_rss() {
                 title: "Hacker Smacker: Friend/foe individual writers on Hacker News".to_string(),
                 author: Some("swyx".to_string()),
                 link: Some("https://github.com/samuelclay/hackersmacker".to_string()),
+                source_link: Some("https://github.com/samuelclay/hackersmacker".to_string()),
                 content: Some("<p>Article URL: <a href=\"https://github.com/samuelclay/hackersmacker\">https://github.com/samuelclay/hackersmacker</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288627\">https://news.ycombinator.com/item?id=37288627</a></p>\n<p>Points: 36</p>\n<p># Comments: 14</p>".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T01:33:24+00:00").unwrap()),
             },
@@ -47,6 +43,7 @@ async fn fetch_feed_items_rss() {
                 title: "Writing Linux Modules in Ada – Part 1".to_string(),
                 author: Some("slondr".to_string()),
                 link: Some("http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1".to_string()),
+                source_link: Some("http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1".to_string()),
                 content: Some("<p>Article URL: <a href=\"http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1\">http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288446\">https://news.ycombinator.com/item?id=37288446</a></p>\n<p>Points: 27</p>\n<p># Comments: 5</p>".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T01:05:24+00:00").unwrap()),
             },
@@ -54,6 +51,7 @@ async fn fetch_feed_items_rss() {
                 title: "Federal study links testicular cancer to ‘forever chemicals’".to_string(),
                 author: Some("EA-3167".to_string()),
                 link: Some("https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/".to_string()),
+                source_link: Some("https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/".to_string()),
                 content: Some("<p>Article URL: <a href=\"https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/\">https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288208\">https://news.ycombinator.com/item?id=37288208</a></p>\n<p>Points: 62</p>\n<p># Comments: 15</p>".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T00:32:34+00:00").unwrap()),
             },
@@ -73,6 +71,7 @@ async fn fetch_feed_items_atom() {
                 title: "Hacker Smacker: Friend/foe individual writers on Hacker News".to_string(),
                 author: Some("swyx".to_string()),
                 link: Some("https://github.com/samuelclay/hackersmacker".to_string()),
+                source_link: Some("https://github.com/samuelclay/hackersmacker".to_string()),
                 content: Some("<p>Article URL: <a href=\"https://github.com/samuelclay/hackersmacker\">https://github.com/samuelclay/hackersmacker</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288627\">https://news.ycombinator.com/item?id=37288627</a></p>\n<p>Points: 36</p>\n<p># Comments: 14</p>".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T01:33:24+00:00").unwrap()),
             },
@@ -80,6 +79,7 @@ async fn fetch_feed_items_atom() {
                 title: "Writing Linux Modules in Ada – Part 1".to_string(),
                 author: Some("slondr".to_string()),
                 link: Some("http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1".to_string()),
+                source_link: Some("http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1".to_string()),
                 content: Some("<p>Article URL: <a href=\"http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1\">http://www.nihamkin.com/2016/10/23/writing-linux-modules-in-ada-part-1/#writing-linux-modules-in-ada-part-1</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288446\">https://news.ycombinator.com/item?id=37288446</a></p>\n<p>Points: 27</p>\n<p># Comments: 5</p>".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T01:05:24+00:00").unwrap()),
             },
@@ -87,6 +87,7 @@ async fn fetch_feed_items_atom() {
                 title: "Federal study links testicular cancer to ‘forever chemicals’".to_string(),
                 author: Some("EA-3167".to_string()),
                 link: Some("https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/".to_string()),
+                source_link: Some("https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/".to_string()),
                 content: Some("<p>Article URL: <a href=\"https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/\">https://undark.org/2023/08/22/federal-study-links-testicular-cancer-to-forever-chemicals/</a></p>\n<p>Comments URL: <a href=\"https://news.ycombinator.com/item?id=37288208\">https://news.ycombinator.com/item?id=37288208</a></p>\n<p>Points: 62</p>\n<p># Comments: 15</p>".to_string()),
                 published_at: Some(DateTime::parse_from_rfc3339("2023-08-28T00:32:34+00:00").unwrap()),
             },
@@ -94,3 +95,172 @@ async fn fetch_feed_items_atom() {
         items,
     );
 }
+
+#[test]
+fn parse_relative_feed_link() {
+    let html = fs::read_to_string(fixture("relative-discovery.html")).unwrap();
+    let link = feed::parse_link(&html, "https://example.com/blog/index.html").unwrap();
+    assert_eq!(Some("https://example.com/feed.xml".to_string()), link);
+}
+
+#[tokio::test]
+async fn resolve_relative_item_links_rss() {
+    let items = item::fetch(&fixture("relative-items.rss"), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        Some("https://example.com/blog/posts/first".to_string()),
+        items[0].link
+    );
+    assert_eq!(Some("posts/first".to_string()), items[0].source_link);
+    assert_eq!(
+        Some("<p><img src=\"https://example.com/images/first.png\"> <a href=\"https://other.example.org/\">Elsewhere</a> <a href=\"https://example.com/blog/about\">About</a></p>".to_string()),
+        items[0].content
+    );
+}
+
+#[tokio::test]
+async fn resolve_relative_item_links_atom() {
+    let items = item::fetch(&fixture("relative-items.atom"), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        Some("https://example.com/blog/posts/second".to_string()),
+        items[0].link
+    );
+    assert_eq!(Some("posts/second".to_string()), items[0].source_link);
+    assert_eq!(
+        Some("<p><img src=\"https://cdn.example.com/images/cover.png\"> <a href=\"https://cdn.example.com/about\">About</a></p>".to_string()),
+        items[0].content
+    );
+}
+
+#[tokio::test]
+async fn discover_feeds_from_link_tags() {
+    let candidates = feed::discover(&fixture("discovery.html"), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        vec![
+            FeedCandidate {
+                link: "https://example.com/blog/feed.xml".to_string(),
+                title: Some("Posts (RSS)".to_string()),
+                format: FeedFormat::Rss,
+            },
+            FeedCandidate {
+                link: "https://example.com/blog/atom.xml".to_string(),
+                title: Some("Posts (Atom)".to_string()),
+                format: FeedFormat::Atom,
+            },
+            FeedCandidate {
+                link: "https://example.com/blog/feed.json".to_string(),
+                title: None,
+                format: FeedFormat::Json,
+            },
+        ],
+        candidates
+    );
+}
+
+#[tokio::test]
+async fn discover_feeds_from_anchors() {
+    let candidates = feed::discover(&fixture("discovery-anchors.html"), None)
+        .await
+        .unwrap();
+    assert_eq!(
+        vec![FeedCandidate {
+            link: "src/tests/fixtures/hnrss-org-frontpage.rss".to_string(),
+            title: Some("Hacker News: Front Page".to_string()),
+            format: FeedFormat::Rss,
+        }],
+        candidates
+    );
+
+    let candidates = feed::discover(&fixture("hnrss-org-frontpage.atom"), None)
+        .await
+        .unwrap();
+    assert_eq!(FeedFormat::Atom, candidates[0].format);
+}
+
+#[test]
+fn parse_feed_metadata() {
+    let parse = |path: &str| {
+        let feed = fs::read_to_string(fixture(path))
+            .unwrap()
+            .parse::<SyndicationFeed>()
+            .unwrap();
+        feed::parse_metadata(&fixture(path), &feed)
+    };
+
+    assert_eq!(
+        FeedMetadata {
+            site_url: Some("https://news.ycombinator.com/".to_string()),
+            description: Some("Hacker News RSS".to_string()),
+            ..Default::default()
+        },
+        parse("hnrss-org-frontpage.rss")
+    );
+    assert_eq!(
+        FeedMetadata {
+            site_url: Some("https://example.com/".to_string()),
+            description: Some("Notes on examples".to_string()),
+            image: Some("https://example.com/blog/images/logo.png".to_string()),
+            language: Some("en".to_string()),
+            author: None,
+        },
+        parse("relative-items.atom")
+    );
+
+    let feed = r#"<rss version="2.0"><channel>
+        <title>Notes</title>
+        <link> </link>
+        <description>Notes without a site</description>
+        <image><url>logo.png</url><title>Notes</title><link> </link></image>
+    </channel></rss>"#
+        .parse::<SyndicationFeed>()
+        .unwrap();
+    assert_eq!(
+        FeedMetadata {
+            site_url: None,
+            description: Some("Notes without a site".to_string()),
+            image: Some("https://example.com/logo.png".to_string()),
+            ..Default::default()
+        },
+        feed::parse_metadata("https://example.com/feed.xml", &feed)
+    );
+}
+
+#[tokio::test]
+async fn skip_broken_feeds() {
+    let conn = connection();
+    let alice = user(&conn, "alice");
+
+    // The feed is served from a copy so that the test can break it.
+    let broken = env::temp_dir().join(format!("collie-broken-feed-{}.rss", std::process::id()));
+    fs::copy(fixture("hnrss-org-frontpage.rss"), &broken).unwrap();
+    for link in [
+        broken.to_str().unwrap().to_string(),
+        fixture("hnrss-org-frontpage.atom"),
+    ] {
+        feed::create(
+            &conn,
+            alice,
+            &FeedToCreate {
+                title: String::new(),
+                link,
+                fetch_old_items: true,
+                selectors: None,
+            },
+            None,
+        )
+        .await
+        .unwrap();
+    }
+    fs::write(&broken, "<html></html>").unwrap();
+
+    let worker = Worker::new(conn.clone(), None);
+    assert_eq!(3, worker.execute().await.unwrap().len());
+
+    fs::remove_file(&broken).unwrap();
+    assert_eq!(0, worker.execute().await.unwrap().len());
+}
@@ -0,0 +1,106 @@
+use pretty_assertions::assert_eq;
+use rusqlite::Connection;
+use std::sync::{Arc, Mutex};
+
+use super::common::{connection, fixture, migrate, user};
+use crate::model::feed::FeedToCreate;
+use crate::model::item::{ItemReadOption, ItemStatus, ItemToUpdateAll};
+use crate::repository::database::{rebuild_legacy_tables, DbConnection};
+use crate::service::{feed, item, user};
+use crate::worker::Worker;
+
+async fn subscribe(conn: &DbConnection, user: i32) {
+    feed::create(
+        conn,
+        user,
+        &FeedToCreate {
+            title: String::new(),
+            link: fixture("hnrss-org-frontpage.rss"),
+            fetch_old_items: true,
+            selectors: None,
+        },
+        None,
+    )
+    .await
+    .unwrap();
+}
+
+fn unread() -> ItemReadOption {
+    ItemReadOption {
+        status: Some(ItemStatus::Unread),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn share_feed_between_users() {
+    let conn = connection();
+
+    let alice = user(&conn, "alice");
+    let bob = user(&conn, "bob");
+    subscribe(&conn, alice).await;
+    subscribe(&conn, bob).await;
+
+    let worker = Worker::new(conn.clone(), None);
+    assert_eq!(6, worker.execute().await.unwrap().len());
+    assert_eq!(0, worker.execute().await.unwrap().len());
+
+    item::update_all(
+        &conn,
+        alice,
+        &ItemToUpdateAll {
+            status: Some(ItemStatus::Read),
+            is_saved: None,
+            opt: None,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(0, item::count_all(&conn, alice, &unread()).unwrap());
+    assert_eq!(3, item::count_all(&conn, bob, &unread()).unwrap());
+    assert_eq!(1, feed::read_all(&conn, alice).unwrap().len());
+}
+
+#[tokio::test]
+async fn rebuild_legacy_database() {
+    let db = Connection::open_in_memory().unwrap();
+    db.execute_batch(
+        r#"
+        CREATE TABLE "feeds" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "title" text NOT NULL, "link" text NOT NULL, "status" text CHECK ("status" IN ('subscribed', 'unsubscribed')) NOT NULL DEFAULT 'subscribed', "checked_at" datetime_text NOT NULL, "fetch_old_items" boolean NOT NULL DEFAULT TRUE, CONSTRAINT "uk_feeds_title_link" UNIQUE ("title", "link") );
+        CREATE TABLE "items" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "fingerprint" text NOT NULL UNIQUE, "author" text, "title" text NOT NULL, "description" text NOT NULL, "link" text NOT NULL, "status" text CHECK ("status" IN ('unread', 'read')) NOT NULL DEFAULT 'unread', "is_saved" integer CHECK ("is_saved" IN (0, 1)) NOT NULL DEFAULT 0, "published_at" datetime_text NOT NULL, "feed" integer NOT NULL, FOREIGN KEY ("feed") REFERENCES "feeds" ("id") ON DELETE CASCADE ON UPDATE CASCADE );
+        INSERT INTO "items" ("fingerprint", "title", "description", "link", "published_at", "feed") VALUES ('fingerprint', 'Title', '', '#', '2023-08-28T00:00:00+00:00', 1);
+        "#,
+    )
+    .unwrap();
+    db.execute(
+        r#"INSERT INTO "feeds" ("title", "link", "checked_at") VALUES ('Hacker News: Front Page', ?1, '2023-08-28T00:00:00+00:00')"#,
+        [fixture("hnrss-org-frontpage.atom")],
+    )
+    .unwrap();
+
+    migrate(&db);
+    rebuild_legacy_tables(&db).unwrap();
+    rebuild_legacy_tables(&db).unwrap();
+    let indexes: i64 = db
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name LIKE 'idx_items_%'",
+            [],
+            |x| x.get(0),
+        )
+        .unwrap();
+    assert_eq!(3, indexes);
+    let conn = Arc::new(Mutex::new(db));
+
+    let alice = user(&conn, "alice");
+    let bob = user(&conn, "bob");
+    assert_eq!(1, user::adopt(&conn, alice).unwrap());
+    assert_eq!(1, item::count_all(&conn, alice, &unread()).unwrap());
+
+    subscribe(&conn, alice).await;
+    subscribe(&conn, bob).await;
+    assert_eq!(2, feed::read_all(&conn, alice).unwrap().len());
+    assert_eq!(1, feed::read_all(&conn, bob).unwrap().len());
+
+    Worker::new(conn.clone(), None).execute().await.unwrap();
+    assert_eq!(3, item::count_all(&conn, bob, &unread()).unwrap());
+}
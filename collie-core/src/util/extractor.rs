@@ -0,0 +1,122 @@
+use ego_tree::NodeId;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use std::{collections::HashMap, sync::OnceLock};
+
+use super::link;
+
+/// Extracted articles shorter than this are more likely navigation than content.
+const MIN_TEXT_LENGTH: usize = 200;
+
+/// Paragraphs shorter than this don't count towards the score of their container.
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+/// Finds the main article of a page the way readability does: paragraphs give points
+/// to their parents and grandparents, class names and ids nudge the score, and the
+/// density of links takes it down. Returns the inner HTML of the best container with
+/// links resolved against `page`, or `None` when nothing looks like an article.
+pub fn extract(html: &str, page: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let paragraph = Selector::parse("p, pre, td").unwrap();
+
+    let mut scores: HashMap<NodeId, f64> = HashMap::new();
+    for element in document.select(&paragraph) {
+        let text = element.text().collect::<String>();
+        let length = text.trim().chars().count();
+        if length < MIN_PARAGRAPH_LENGTH {
+            continue;
+        }
+
+        let score = 1.0 + text.matches(',').count() as f64 + (length as f64 / 100.0).min(3.0);
+        let mut ancestors = element.ancestors().filter_map(ElementRef::wrap);
+        if let Some(parent) = ancestors.next() {
+            *scores
+                .entry(parent.id())
+                .or_insert_with(|| initial_score(&parent)) += score;
+        }
+        if let Some(grandparent) = ancestors.next() {
+            *scores
+                .entry(grandparent.id())
+                .or_insert_with(|| initial_score(&grandparent)) += score / 2.0;
+        }
+    }
+
+    let (best, _) = scores
+        .iter()
+        .filter_map(|(id, score)| {
+            let element = ElementRef::wrap(document.tree.get(*id)?)?;
+            Some((element, score * (1.0 - link_density(&element))))
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+
+    if best.text().collect::<String>().trim().chars().count() < MIN_TEXT_LENGTH {
+        return None;
+    }
+
+    let base = link::base(&[base_href(&document), Some(page)]);
+    let content = best.inner_html();
+    Some(match base {
+        Some(base) => link::resolve_html(content.trim(), &base),
+        None => content.trim().to_string(),
+    })
+}
+
+fn initial_score(element: &ElementRef) -> f64 {
+    static POSITIVE: OnceLock<Regex> = OnceLock::new();
+    static NEGATIVE: OnceLock<Regex> = OnceLock::new();
+    let positive = POSITIVE.get_or_init(|| {
+        Regex::new(r"(?i)article|body|content|entry|main|page|post|text|blog|story").unwrap()
+    });
+    let negative = NEGATIVE.get_or_init(|| {
+        Regex::new(
+            r"(?i)comment|meta|footer|footnote|sidebar|nav|menu|share|related|promo|advert|banner|social|widget",
+        )
+        .unwrap()
+    });
+
+    let mut score = match element.value().name() {
+        "article" | "main" => 10.0,
+        "div" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "form" | "ol" | "ul" | "li" | "aside" | "nav" | "header" | "footer" => -3.0,
+        _ => 0.0,
+    };
+
+    for name in [element.value().attr("class"), element.value().attr("id")]
+        .into_iter()
+        .flatten()
+    {
+        if negative.is_match(name) {
+            score -= 25.0;
+        }
+        if positive.is_match(name) {
+            score += 25.0;
+        }
+    }
+
+    score
+}
+
+fn link_density(element: &ElementRef) -> f64 {
+    let anchor = Selector::parse("a").unwrap();
+
+    let length = element.text().map(str::len).sum::<usize>();
+    if length == 0 {
+        return 1.0;
+    }
+    let link_length = element
+        .select(&anchor)
+        .flat_map(|x| x.text())
+        .map(str::len)
+        .sum::<usize>();
+
+    link_length as f64 / length as f64
+}
+
+fn base_href(document: &Html) -> Option<&str> {
+    let selector = Selector::parse("base[href]").unwrap();
+    document
+        .select(&selector)
+        .next()
+        .and_then(|x| x.value().attr("href"))
+}
@@ -6,17 +6,16 @@ pub async fn get(link: &str, _proxy: Option<&str>) -> Result<String> {
     Ok(fs::read_to_string(link)?)
 }
 
+/// Fetches binary content, along with its content type when the server tells it.
+#[cfg(test)]
+pub async fn get_bytes(link: &str, _proxy: Option<&str>) -> Result<(Option<String>, Vec<u8>)> {
+    use std::fs;
+    Ok((None, fs::read(link)?))
+}
+
 #[cfg(not(test))]
 pub async fn get(link: &str, proxy: Option<&str>) -> Result<String> {
-    let client = if let Some(proxy_url) = proxy {
-        match reqwest::Proxy::all(proxy_url) {
-            Ok(p) => reqwest::Client::builder().proxy(p).build()?,
-            Err(_) => reqwest::Client::new(),
-        }
-    } else {
-        reqwest::Client::new()
-    };
-    Ok(client
+    Ok(client(proxy)?
         .get(link)
         .header("User-Agent", "Mozilla/5.0")
         .send()
@@ -24,3 +23,30 @@ pub async fn get(link: &str, proxy: Option<&str>) -> Result<String> {
         .text()
         .await?)
 }
+
+/// Fetches binary content, along with its content type when the server tells it.
+#[cfg(not(test))]
+pub async fn get_bytes(link: &str, proxy: Option<&str>) -> Result<(Option<String>, Vec<u8>)> {
+    let response = client(proxy)?
+        .get(link)
+        .header("User-Agent", "Mozilla/5.0")
+        .send()
+        .await?
+        .error_for_status()?;
+    let mime = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .map(|x| x.split(';').next().unwrap_or_default().trim().to_string());
+
+    Ok((mime, response.bytes().await?.to_vec()))
+}
+
+/// Builds a client going through the proxy, or a direct one when the proxy is invalid.
+#[cfg(not(test))]
+fn client(proxy: Option<&str>) -> Result<reqwest::Client> {
+    Ok(match proxy.map(reqwest::Proxy::all) {
+        Some(Ok(proxy)) => reqwest::Client::builder().proxy(proxy).build()?,
+        _ => reqwest::Client::new(),
+    })
+}
@@ -0,0 +1,135 @@
+use ego_tree::NodeRef;
+use scraper::{Html, Node};
+use url::Url;
+
+use super::sanitizer::{escape, VOID_TAGS};
+
+const URL_ATTRIBUTES: [&str; 2] = ["href", "src"];
+
+/// Elements whose text is written as it is rather than escaped.
+const RAW_TEXT_TAGS: [&str; 2] = ["script", "style"];
+
+pub fn is_absolute(link: &str) -> bool {
+    Url::parse(link.trim()).is_ok()
+}
+
+/// Resolves a possibly relative link against a base URL. Absolute links, and any link
+/// when the base isn't a URL, are returned as they are.
+pub fn resolve(base: &str, link: &str) -> String {
+    let link = link.trim();
+    if Url::parse(link).is_ok() {
+        return link.to_string();
+    }
+
+    match Url::parse(base).and_then(|base| base.join(link)) {
+        Ok(resolved) => resolved.to_string(),
+        Err(_) => link.to_string(),
+    }
+}
+
+/// Picks the first candidate that resolves to an absolute URL, each candidate being
+/// resolved against the ones after it.
+pub fn base(candidates: &[Option<&str>]) -> Option<String> {
+    let mut base: Option<String> = None;
+
+    for candidate in candidates.iter().rev().flatten() {
+        let resolved = match &base {
+            Some(base) => resolve(base, candidate),
+            None => candidate.to_string(),
+        };
+        if Url::parse(&resolved).is_ok() {
+            base = Some(resolved);
+        }
+    }
+
+    base
+}
+
+/// Resolves the `href` and `src` attributes in an HTML fragment against a base URL.
+pub fn resolve_html(html: &str, base: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+
+    // Written off a stack rather than recursively, as deep nesting would overflow the call
+    // stack.
+    let mut resolved = String::new();
+    let mut stack = fragment
+        .root_element()
+        .children()
+        .rev()
+        .map(Step::Write)
+        .collect::<Vec<_>>();
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Write(node) => write_node(node, base, &mut resolved, &mut stack),
+            Step::Close(name) => {
+                resolved.push_str("</");
+                resolved.push_str(name);
+                resolved.push('>');
+            }
+        }
+    }
+
+    resolved
+}
+
+enum Step<'a> {
+    Write(NodeRef<'a, Node>),
+    Close(&'a str),
+}
+
+/// Writes a node without its children, which are pushed on the stack to be written next.
+fn write_node<'a>(
+    node: NodeRef<'a, Node>,
+    base: &str,
+    out: &mut String,
+    stack: &mut Vec<Step<'a>>,
+) {
+    match node.value() {
+        Node::Text(text) => {
+            let is_raw = node
+                .parent()
+                .and_then(|x| x.value().as_element())
+                .is_some_and(|x| RAW_TEXT_TAGS.contains(&x.name()));
+            if is_raw {
+                out.push_str(text);
+            } else {
+                escape(text, false, out);
+            }
+        }
+        Node::Comment(comment) => {
+            out.push_str("<!--");
+            out.push_str(comment);
+            out.push_str("-->");
+        }
+        Node::Element(element) => {
+            let name = element.name();
+
+            // Attributes aren't kept in source order, so sort them for stable output.
+            let mut attrs = element.attrs().collect::<Vec<_>>();
+            attrs.sort_unstable();
+
+            out.push('<');
+            out.push_str(name);
+            for (key, value) in attrs {
+                out.push(' ');
+                out.push_str(key);
+                out.push_str("=\"");
+                if URL_ATTRIBUTES.contains(&key) {
+                    escape(&resolve(base, value), true, out);
+                } else {
+                    escape(value, true, out);
+                }
+                out.push('"');
+            }
+            out.push('>');
+
+            if VOID_TAGS.contains(&name) {
+                return;
+            }
+
+            stack.push(Step::Close(name));
+            stack.extend(node.children().rev().map(Step::Write));
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,204 @@
+use regex::Regex;
+use std::sync::OnceLock;
+use url::{form_urlencoded, Url};
+
+use crate::model::feed::{FeedCandidate, FeedFormat};
+
+/// Maps pages of a known site to their feeds, for sites that don't advertise a usable
+/// feed on the pages people share.
+pub trait Resolver: Send + Sync {
+    /// Returns the feed of the page at `url`, whose content is `html`, if the page is
+    /// one this resolver knows.
+    fn resolve(&self, url: &Url, html: &str) -> Option<FeedCandidate>;
+}
+
+/// Resolvers consulted in the order they were registered.
+pub struct Registry {
+    resolvers: Vec<Box<dyn Resolver>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+            .register(YouTube)
+            .register(Reddit)
+            .register(GitHub)
+            .register(Mastodon)
+    }
+}
+
+impl Registry {
+    /// Creates a registry without any resolver, not even the built-in ones.
+    pub fn new() -> Self {
+        Self { resolvers: vec![] }
+    }
+
+    pub fn register(mut self, resolver: impl Resolver + 'static) -> Self {
+        self.resolvers.push(Box::new(resolver));
+        self
+    }
+
+    pub fn resolve(&self, link: &str, html: &str) -> Option<FeedCandidate> {
+        let url = Url::parse(link.trim()).ok()?;
+        self.resolvers.iter().find_map(|x| x.resolve(&url, html))
+    }
+}
+
+pub struct YouTube;
+
+impl Resolver for YouTube {
+    fn resolve(&self, url: &Url, html: &str) -> Option<FeedCandidate> {
+        if !is_host(url, &["youtube.com", "www.youtube.com", "m.youtube.com"]) {
+            return None;
+        }
+
+        let query = if let Some((_, list)) = url.query_pairs().find(|(key, _)| key == "list") {
+            form_urlencoded::Serializer::new(String::new())
+                .append_pair("playlist_id", &list)
+                .finish()
+        } else {
+            let segments = segments(url);
+            match segments.as_slice() {
+                ["channel", id, ..] => format!("channel_id={id}"),
+                ["user", name, ..] => format!("user={name}"),
+                [x, ..] if x.starts_with('@') || *x == "c" => {
+                    format!("channel_id={}", channel_id(html)?)
+                }
+                _ => return None,
+            }
+        };
+
+        Some(candidate(
+            format!("https://www.youtube.com/feeds/videos.xml?{query}"),
+            FeedFormat::Atom,
+        ))
+    }
+}
+
+pub struct Reddit;
+
+impl Resolver for Reddit {
+    fn resolve(&self, url: &Url, _html: &str) -> Option<FeedCandidate> {
+        if !is_host(url, &["reddit.com", "www.reddit.com", "old.reddit.com"]) {
+            return None;
+        }
+
+        match segments(url).as_slice() {
+            [kind @ ("r" | "u" | "user"), name, rest @ ..] if !name.is_empty() => {
+                let mut path = format!("{kind}/{name}");
+                if let [sort @ ("hot" | "new" | "top" | "rising"), ..] = rest {
+                    path = format!("{path}/{sort}");
+                }
+                Some(candidate(
+                    format!("https://www.reddit.com/{path}.rss"),
+                    FeedFormat::Atom,
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+pub struct GitHub;
+
+impl Resolver for GitHub {
+    fn resolve(&self, url: &Url, _html: &str) -> Option<FeedCandidate> {
+        if !is_host(url, &["github.com", "www.github.com"]) {
+            return None;
+        }
+
+        match segments(url).as_slice() {
+            [owner, repo, ..] if !is_reserved(owner) => Some(candidate(
+                format!(
+                    "https://github.com/{owner}/{}/releases.atom",
+                    repo.trim_end_matches(".git")
+                ),
+                FeedFormat::Atom,
+            )),
+            [owner] if !is_reserved(owner) => Some(candidate(
+                format!("https://github.com/{owner}.atom"),
+                FeedFormat::Atom,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Recognized by the markup Mastodon serves rather than by the host, since there are
+/// countless instances.
+pub struct Mastodon;
+
+impl Resolver for Mastodon {
+    fn resolve(&self, url: &Url, html: &str) -> Option<FeedCandidate> {
+        if !html.contains("id=\"mastodon\"") {
+            return None;
+        }
+
+        match segments(url).as_slice() {
+            [account] if account.len() > 1 && account.starts_with('@') => Some(candidate(
+                format!("{}/{account}.rss", url.origin().ascii_serialization()),
+                FeedFormat::Rss,
+            )),
+            _ => None,
+        }
+    }
+}
+
+fn candidate(link: String, format: FeedFormat) -> FeedCandidate {
+    FeedCandidate {
+        link,
+        title: None,
+        format,
+    }
+}
+
+fn is_host(url: &Url, hosts: &[&str]) -> bool {
+    url.host_str()
+        .is_some_and(|host| hosts.iter().any(|x| host.eq_ignore_ascii_case(x)))
+}
+
+fn segments(url: &Url) -> Vec<&str> {
+    url.path_segments()
+        .map(|x| x.filter(|x| !x.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+/// Top-level paths of GitHub that aren't owners.
+fn is_reserved(owner: &str) -> bool {
+    [
+        "about",
+        "explore",
+        "features",
+        "login",
+        "marketplace",
+        "notifications",
+        "orgs",
+        "pricing",
+        "settings",
+        "sponsors",
+        "topics",
+        "trending",
+    ]
+    .contains(&owner)
+}
+
+/// Finds the id of a YouTube channel in its page, since handles can't be used in feed
+/// links.
+fn channel_id(html: &str) -> Option<String> {
+    static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            r#"<meta itemprop="(?:channelId|identifier)" content="(UC[\w-]{22})""#,
+            r#"<link rel="canonical" href="https://www\.youtube\.com/channel/(UC[\w-]{22})""#,
+            r#""(?:externalId|channelId)":"(UC[\w-]{22})""#,
+        ]
+        .iter()
+        .map(|x| Regex::new(x).unwrap())
+        .collect()
+    });
+
+    patterns
+        .iter()
+        .find_map(|x| x.captures(html))
+        .map(|x| x[1].to_string())
+}
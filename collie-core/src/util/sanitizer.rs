@@ -0,0 +1,332 @@
+use ego_tree::NodeRef;
+use scraper::{node::Element, Html, Node};
+use std::collections::{HashMap, HashSet};
+
+/// Elements dropped together with everything inside them.
+const CLEARED_TAGS: [&str; 11] = [
+    "script", "style", "iframe", "frame", "frameset", "object", "embed", "noscript", "template",
+    "svg", "math",
+];
+
+/// Elements without content or a closing tag.
+pub(crate) const VOID_TAGS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Tokens always added to the `rel` of links, so that pages opened from them can't reach
+/// back to the reader.
+const FORCED_REL: [&str; 2] = ["noopener", "noreferrer"];
+
+const URL_ATTRIBUTES: [&str; 3] = ["href", "src", "cite"];
+
+/// Query parameters that only exist to track where a visit came from.
+const TRACKING_PARAMS: [&str; 7] = [
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid",
+];
+
+/// An allowlist of what survives sanitization. Elements outside of it are unwrapped,
+/// keeping their text, and attributes outside of it are dropped.
+#[derive(Clone, Debug)]
+pub struct SanitizePolicy {
+    pub tags: HashSet<String>,
+    /// Allowed attributes by tag. Those under `*` are allowed on every tag.
+    pub attributes: HashMap<String, HashSet<String>>,
+    /// Schemes allowed in URL attributes. Relative URLs are always allowed.
+    pub url_schemes: HashSet<String>,
+    /// Removes tracking pixels and tracking query parameters from links.
+    pub strip_tracking: bool,
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        let tags = [
+            "a",
+            "abbr",
+            "b",
+            "blockquote",
+            "br",
+            "caption",
+            "cite",
+            "code",
+            "dd",
+            "del",
+            "details",
+            "div",
+            "dl",
+            "dt",
+            "em",
+            "figcaption",
+            "figure",
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "hr",
+            "i",
+            "img",
+            "ins",
+            "kbd",
+            "li",
+            "mark",
+            "ol",
+            "p",
+            "pre",
+            "q",
+            "s",
+            "small",
+            "span",
+            "strong",
+            "sub",
+            "summary",
+            "sup",
+            "table",
+            "tbody",
+            "td",
+            "tfoot",
+            "th",
+            "thead",
+            "tr",
+            "u",
+            "ul",
+        ];
+        let attributes = [
+            ("*", vec!["title", "lang"]),
+            ("a", vec!["href"]),
+            ("img", vec!["src", "alt", "width", "height"]),
+            ("blockquote", vec!["cite"]),
+            ("q", vec!["cite"]),
+            ("ol", vec!["start"]),
+            ("td", vec!["colspan", "rowspan"]),
+            ("th", vec!["colspan", "rowspan"]),
+        ];
+
+        Self {
+            tags: tags.iter().map(|x| x.to_string()).collect(),
+            attributes: attributes
+                .into_iter()
+                .map(|(tag, attrs)| {
+                    (
+                        tag.to_string(),
+                        attrs.into_iter().map(str::to_string).collect(),
+                    )
+                })
+                .collect(),
+            url_schemes: ["http", "https", "mailto"]
+                .iter()
+                .map(|x| x.to_string())
+                .collect(),
+            strip_tracking: true,
+        }
+    }
+}
+
+pub fn sanitize(html: &str, policy: &SanitizePolicy) -> String {
+    let fragment = Html::parse_fragment(html);
+
+    // Nodes are written off a stack rather than recursively, so that deeply nested markup
+    // can't overflow the call stack.
+    let mut sanitized = String::new();
+    let mut stack = fragment
+        .root_element()
+        .children()
+        .rev()
+        .map(Step::Write)
+        .collect::<Vec<_>>();
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Write(node) => write_node(node, policy, &mut sanitized, &mut stack),
+            Step::Close(name) => {
+                sanitized.push_str("</");
+                sanitized.push_str(name);
+                sanitized.push('>');
+            }
+        }
+    }
+
+    sanitized
+}
+
+/// Removes `utm_*` and other tracking parameters from the query of a URL.
+pub fn strip_tracking_params(url: &str) -> String {
+    let (rest, fragment) = match url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (url, None),
+    };
+    let Some((base, query)) = rest.split_once('?') else {
+        return url.to_string();
+    };
+
+    let params = query
+        .split('&')
+        .filter(|param| {
+            let key = param.split('=').next().unwrap_or_default().to_lowercase();
+            !param.is_empty() && !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&&*key)
+        })
+        .collect::<Vec<_>>();
+
+    let mut stripped = base.to_string();
+    if !params.is_empty() {
+        stripped.push('?');
+        stripped.push_str(&params.join("&"));
+    }
+    if let Some(fragment) = fragment {
+        stripped.push('#');
+        stripped.push_str(fragment);
+    }
+
+    stripped
+}
+
+enum Step<'a> {
+    Write(NodeRef<'a, Node>),
+    Close(&'a str),
+}
+
+/// Writes a node without its children, which are pushed on the stack to be written next.
+fn write_node<'a>(
+    node: NodeRef<'a, Node>,
+    policy: &SanitizePolicy,
+    out: &mut String,
+    stack: &mut Vec<Step<'a>>,
+) {
+    match node.value() {
+        Node::Text(text) => escape(text, false, out),
+        Node::Element(element) => {
+            let name = element.name();
+            if CLEARED_TAGS.contains(&name) || (policy.strip_tracking && is_tracking_pixel(element))
+            {
+                return;
+            }
+
+            if !policy.tags.contains(name) {
+                stack.extend(node.children().rev().map(Step::Write));
+                return;
+            }
+
+            // Attributes aren't kept in source order, so sort them for stable output.
+            let mut attrs = element.attrs().collect::<Vec<_>>();
+            attrs.sort_unstable();
+
+            out.push('<');
+            out.push_str(name);
+            let mut rel = None;
+            for (key, value) in attrs {
+                let Some(value) = sanitize_attribute(name, key, value, policy) else {
+                    continue;
+                };
+                if name == "a" && key == "rel" {
+                    rel = Some(value);
+                    continue;
+                }
+
+                out.push(' ');
+                out.push_str(key);
+                out.push_str("=\"");
+                escape(&value, true, out);
+                out.push('"');
+            }
+            if name == "a" {
+                let mut tokens = rel
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .collect::<Vec<_>>();
+                for forced in FORCED_REL {
+                    if !tokens.iter().any(|x| x.eq_ignore_ascii_case(forced)) {
+                        tokens.push(forced);
+                    }
+                }
+
+                out.push_str(" rel=\"");
+                escape(&tokens.join(" "), true, out);
+                out.push('"');
+            }
+            out.push('>');
+
+            if VOID_TAGS.contains(&name) {
+                return;
+            }
+
+            stack.push(Step::Close(name));
+            stack.extend(node.children().rev().map(Step::Write));
+        }
+        _ => {}
+    }
+}
+
+fn sanitize_attribute(
+    tag: &str,
+    key: &str,
+    value: &str,
+    policy: &SanitizePolicy,
+) -> Option<String> {
+    let is_allowed = [tag, "*"].iter().any(|x| {
+        policy
+            .attributes
+            .get(*x)
+            .is_some_and(|attrs| attrs.contains(key))
+    });
+    if !is_allowed {
+        return None;
+    }
+
+    if !URL_ATTRIBUTES.contains(&key) {
+        return Some(value.to_string());
+    }
+
+    if let Some(scheme) = scheme(value) {
+        if !policy.url_schemes.contains(&scheme) {
+            return None;
+        }
+    }
+
+    if policy.strip_tracking {
+        Some(strip_tracking_params(value.trim()))
+    } else {
+        Some(value.trim().to_string())
+    }
+}
+
+/// Reads the scheme the way browsers do, ignoring whitespace and control characters
+/// that would otherwise hide `javascript:` from a naive check.
+fn scheme(url: &str) -> Option<String> {
+    let normalized = url
+        .chars()
+        .filter(|x| !x.is_whitespace() && !x.is_control())
+        .collect::<String>();
+    let end = normalized.find([':', '/', '?', '#'])?;
+
+    if normalized[end..].starts_with(':') {
+        Some(normalized[..end].to_lowercase())
+    } else {
+        None
+    }
+}
+
+fn is_tracking_pixel(element: &Element) -> bool {
+    let is_tiny = |attr: &str| {
+        element
+            .attr(attr)
+            .and_then(|x| x.trim().trim_end_matches("px").parse::<u32>().ok())
+            .is_some_and(|x| x <= 1)
+    };
+
+    element.name() == "img" && is_tiny("width") && is_tiny("height")
+}
+
+/// Escapes text for HTML, quotes included when it goes in an attribute.
+pub fn escape(text: &str, is_attribute: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if is_attribute => out.push_str("&quot;"),
+            '\u{a0}' => out.push_str("&nbsp;"),
+            _ => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+use crate::{
+    error::Result,
+    model::{feed::Feed, syndication::RawItem},
+};
+
+/// Reads the items of feeds of a custom kind, for feeds that aren't on the web: a
+/// directory of Markdown files, a mailbox dump or an internal API.
+#[async_trait]
+pub trait Source: Send + Sync {
+    async fn fetch(&self, feed: &Feed) -> Result<Vec<RawItem>>;
+}
+
+/// Sources by the kind of feed they read. Syndication and scraped feeds are read by the
+/// worker itself, so that feeds sharing a link share a fetch.
+#[derive(Default)]
+pub struct Registry {
+    sources: HashMap<String, Box<dyn Source>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the source of a kind, replacing the one registered before.
+    pub fn register(mut self, kind: &str, source: impl Source + 'static) -> Self {
+        self.sources.insert(kind.to_string(), Box::new(source));
+        self
+    }
+
+    pub fn get(&self, kind: &str) -> Option<&dyn Source> {
+        self.sources.get(kind).map(|x| &**x)
+    }
+}
@@ -1,146 +1,401 @@
 use chrono::DateTime;
+use chrono::Duration;
 use chrono::FixedOffset;
 use chrono::Utc;
 use std::collections::HashMap;
 
 use crate::error::Result;
-use crate::model::feed::FeedStatus;
-use crate::model::feed::FeedToUpdate;
-use crate::model::item::ItemOrder;
-use crate::model::item::ItemReadOption;
+use crate::model::feed::{Feed, FeedKind};
 use crate::model::item::ItemStatus;
 use crate::model::item::ItemToCreate;
-use crate::model::syndication::RawItem;
+use crate::model::retention::RetentionPolicy;
+use crate::model::syndication::{Feed as SyndicationFeed, RawItem};
 use crate::repository::database::DbConnection;
 use crate::service::feed;
+use crate::service::history;
+use crate::service::icon;
 use crate::service::item;
+use crate::service::retention;
+use crate::service::rule;
+use crate::service::tag;
+use crate::util::extractor;
+use crate::util::fetcher;
+use crate::util::sanitizer::{self, SanitizePolicy};
+use crate::util::source::{self, Source};
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub inserted: Vec<ItemToCreate>,
+    /// How many new items each rule matched, by rule id.
+    pub matches: HashMap<i32, usize>,
+}
 
 pub struct Worker {
     conn: DbConnection,
     proxy: Option<String>,
+    retention: RetentionPolicy,
+    sanitize: Option<SanitizePolicy>,
+    icon_interval: Duration,
+    history_window: Duration,
+    purge_window: Duration,
+    sources: source::Registry,
 }
 
 impl Worker {
     pub fn new(conn: DbConnection, proxy: Option<String>) -> Self {
-        Self { conn, proxy }
+        Self {
+            conn,
+            proxy,
+            retention: RetentionPolicy::default(),
+            sanitize: Some(SanitizePolicy::default()),
+            icon_interval: Duration::days(7),
+            history_window: Duration::days(30),
+            purge_window: Duration::days(90),
+            sources: source::Registry::new(),
+        }
+    }
+
+    /// Registers the source reading feeds of a custom kind.
+    pub fn source(mut self, kind: &str, source: impl Source + 'static) -> Self {
+        self.sources = self.sources.register(kind, source);
+        self
+    }
+
+    /// Sets how long feed icons are kept before being looked for again.
+    pub fn icon_interval(mut self, interval: Duration) -> Self {
+        self.icon_interval = interval;
+        self
+    }
+
+    /// Looks for the icons of feeds not checked within the icon interval, returning how
+    /// many of them have an icon now.
+    pub async fn refresh_icons(&self) -> Result<usize> {
+        icon::refresh_all(&self.conn, self.icon_interval, self.proxy.as_deref()).await
+    }
+
+    /// Sets how descriptions are sanitized before being stored. `None` stores them
+    /// verbatim, leaving it to readers to sanitize on read.
+    pub fn sanitize(mut self, policy: Option<SanitizePolicy>) -> Self {
+        self.sanitize = policy;
+        self
+    }
+
+    /// Sets the retention policy applied to feeds without their own.
+    pub fn retention(mut self, policy: RetentionPolicy) -> Self {
+        self.retention = policy;
+        self
+    }
+
+    /// Sets how long bulk updates of items can be undone.
+    pub fn history_window(mut self, window: Duration) -> Self {
+        self.history_window = window;
+        self
+    }
+
+    /// Sets how long purged items are kept from coming back.
+    pub fn purge_window(mut self, window: Duration) -> Self {
+        self.purge_window = window;
+        self
+    }
+
+    /// Forgets the read history older than the history window, returning how many
+    /// entries were deleted.
+    pub fn prune_history(&self) -> Result<usize> {
+        history::prune(&self.conn, self.history_window)
+    }
+
+    /// Deletes items outside of their retention policies, returning how many were deleted.
+    /// The read history and purges older than the purge window are pruned along the way.
+    pub fn cleanup(&self) -> Result<usize> {
+        let _ = self.prune_history();
+        let _ = retention::forget(&self.conn, self.purge_window);
+        retention::execute(&self.conn, &self.retention)
     }
 
     pub async fn execute(&self) -> Result<Vec<ItemToCreate>> {
-        let pairs = self.get_links_to_check();
+        Ok(self.execute_with_report().await?.inserted)
+    }
 
-        let mut inserted = vec![];
+    pub async fn execute_with_report(&self) -> Result<Report> {
+        let links = self.get_links_to_check();
 
-        let feed_ids_to_check: Vec<i32> = pairs
-            .iter()
-            .filter_map(|(id, _, fetch_old_items)| if !fetch_old_items { Some(*id) } else { None })
-            .collect();
+        let mut report = Report::default();
+        let mut articles = HashMap::new();
+
+        // Users subscribing to the same link share a single fetch.
+        for (link, feeds) in links {
+            let mut syndicated = vec![];
+            let mut scraped = vec![];
+            for feed in feeds {
+                match &feed.kind {
+                    FeedKind::Syndication => syndicated.push(feed),
+                    FeedKind::Scraped => scraped.push(feed),
+                    // Feeds of unregistered kinds, or whose source fails, are left alone
+                    // rather than failing the whole run.
+                    FeedKind::Custom(kind) => {
+                        let Some(source) = self.sources.get(kind) else {
+                            continue;
+                        };
+                        if let Ok(items) = source.fetch(&feed).await {
+                            self.insert_feed_items(&feed, &items, &mut articles, &mut report)
+                                .await;
+                        }
+                    }
+                }
+            }
 
-        let most_recent_items = if !feed_ids_to_check.is_empty() {
-            self.get_most_recent_items(&feed_ids_to_check)
-                .unwrap_or_default()
+            if syndicated.is_empty() && scraped.is_empty() {
+                continue;
+            }
+
+            // Links that fail to fetch or parse are tried again on the next run, without
+            // holding back the other links.
+            let Ok(content) = fetcher::get(&link, self.proxy.as_deref()).await else {
+                continue;
+            };
+            if !syndicated.is_empty() {
+                if let Ok(syndication) = content.parse::<SyndicationFeed>() {
+                    let items = item::parse(&link, &syndication);
+
+                    let ids = syndicated.iter().map(|x| x.id).collect::<Vec<_>>();
+                    let metadata = feed::parse_metadata(&link, &syndication);
+                    let _ = feed::update_metadata(&self.conn, &ids, &metadata);
+
+                    for feed in syndicated {
+                        self.insert_feed_items(&feed, &items, &mut articles, &mut report)
+                            .await;
+                    }
+                }
+            }
+
+            // Feeds scraping the same page may still read it with different selectors.
+            for feed in scraped {
+                let items = match feed::read_selectors(&self.conn, feed.id) {
+                    Ok(Some(selectors)) => {
+                        item::scrape(&link, &content, &selectors).unwrap_or_default()
+                    }
+                    _ => continue,
+                };
+                self.insert_feed_items(&feed, &items, &mut articles, &mut report)
+                    .await;
+            }
+        }
+
+        let _ = rule::record_matches(&self.conn, &report.matches);
+        let _ = self.refresh_icons().await;
+        Ok(report)
+    }
+
+    async fn insert_feed_items(
+        &self,
+        feed: &Feed,
+        items: &[RawItem],
+        articles: &mut HashMap<String, Option<String>>,
+        report: &mut Report,
+    ) {
+        let fetch_old_items = feed.fetch_old_items;
+        let most_recent = if fetch_old_items {
+            None
         } else {
-            HashMap::new()
+            self.get_most_recent_published_at(feed.id)
         };
 
-        for (feed, link, fetch_old_items) in pairs {
-            let items = item::fetch(&link, self.proxy.as_deref()).await?;
-
-            let mut filtered_items = if !fetch_old_items && !most_recent_items.contains_key(&feed) {
-                items
-                    .into_iter()
-                    .max_by_key(|x| x.published_at)
-                    .into_iter()
-                    .collect()
-            } else {
-                items
-                    .into_iter()
-                    .filter(|item| {
-                        most_recent_items.get(&feed).map_or(true, |most_recent| {
-                            item.published_at
-                                .map_or(false, |published_at| published_at > *most_recent)
-                        }) || fetch_old_items
-                    })
-                    .collect::<Vec<_>>()
-            };
+        // Pages often show no dates, so undated items of scraped feeds are told apart by
+        // where they stand: those above the top item of the last poll are new.
+        let unseen = if feed.kind == FeedKind::Scraped {
+            self.mark_seen(feed.id, items)
+        } else {
+            items.len()
+        };
+        let is_unseen_scrape = |index: usize, item: &RawItem| {
+            feed.kind == FeedKind::Scraped && item.published_at.is_none() && index < unseen
+        };
+
+        let mut filtered_items = if !fetch_old_items && most_recent.is_none() {
+            // Reversed so that the first of equally recent items wins, which is the top of
+            // the page when a scraped feed has no dates.
+            items
+                .iter()
+                .rev()
+                .max_by_key(|x| x.published_at)
+                .into_iter()
+                .collect()
+        } else {
+            items
+                .iter()
+                .enumerate()
+                .filter(|(index, item)| {
+                    most_recent.is_none_or(|most_recent| {
+                        item.published_at
+                            .is_some_and(|published_at| published_at > most_recent)
+                    }) || fetch_old_items
+                        || is_unseen_scrape(*index, item)
+                })
+                .map(|(_, item)| item)
+                .collect::<Vec<_>>()
+        };
+
+        filtered_items.sort_by_key(|x| x.published_at);
+        self.insert_new_items(feed, &filtered_items, articles, report)
+            .await;
+    }
 
-            filtered_items.sort_by_key(|x| x.published_at);
-            inserted.extend(self.insert_new_items(feed, &filtered_items));
+    /// Moves the seen mark of a scraped feed to the top of its page, returning how many
+    /// items are above the previous one. Every item counts as unseen when the previous
+    /// one is gone from the page, leaving fingerprints to skip those already stored.
+    fn mark_seen(&self, feed: i32, items: &[RawItem]) -> usize {
+        let current = Utc::now().fixed_offset();
+        let fingerprints = items
+            .iter()
+            .map(|x| self.to_item(feed, x, current).fingerprint())
+            .collect::<Vec<_>>();
+
+        let mark = feed::read_seen_mark(&self.conn, feed).unwrap_or_default();
+        if let Some(top) = fingerprints.first() {
+            let _ = feed::update_seen_mark(&self.conn, feed, top);
         }
 
-        Ok(inserted)
+        mark.and_then(|mark| fingerprints.iter().position(|x| *x == mark))
+            .unwrap_or(items.len())
     }
 
-    fn get_links_to_check(&self) -> Vec<(i32, String, bool)> {
-        if let Ok(feeds) = feed::read_all(&self.conn) {
+    fn get_links_to_check(&self) -> Vec<(String, Vec<Feed>)> {
+        if let Ok(feeds) = feed::read_all_subscribed(&self.conn) {
             let current = Utc::now().fixed_offset();
-            let filtered = feeds.iter().filter(|x| x.status == FeedStatus::Subscribed);
-
-            filtered
-                .map(|x| {
-                    let _ = feed::update(
-                        &self.conn,
-                        &(FeedToUpdate {
-                            id: x.id,
-                            title: None,
-                            link: None,
-                            status: None,
-                            checked_at: Some(current),
-                            fetch_old_items: None,
-                        }),
-                    );
-                    (x.id, x.link.clone(), x.fetch_old_items)
-                })
-                .collect()
+            let ids = feeds.iter().map(|x| x.id).collect::<Vec<_>>();
+            let _ = feed::update_checked_at(&self.conn, &ids, current);
+
+            let mut links: Vec<(String, Vec<Feed>)> = vec![];
+            for x in feeds {
+                match links.iter_mut().find(|(link, _)| *link == x.link) {
+                    Some((_, feeds)) => feeds.push(x),
+                    None => links.push((x.link.clone(), vec![x])),
+                }
+            }
+
+            links
         } else {
             vec![]
         }
     }
 
-    fn insert_new_items(&self, feed: i32, items: &[RawItem]) -> Vec<ItemToCreate> {
+    async fn insert_new_items(
+        &self,
+        feed: &Feed,
+        items: &[&RawItem],
+        articles: &mut HashMap<String, Option<String>>,
+        report: &mut Report,
+    ) {
+        let id = feed.id;
         let current = Utc::now().fixed_offset();
+        let matchers = rule::matchers(&self.conn, id).unwrap_or_default();
+
+        let args = items
+            .iter()
+            .map(|x| self.to_item(id, x, current))
+            .collect::<Vec<_>>();
+        let fingerprints = args.iter().map(|x| x.fingerprint()).collect::<Vec<_>>();
+        let purged =
+            retention::purged_fingerprints(&self.conn, id, &fingerprints).unwrap_or_default();
+
+        for mut arg in args {
+            let fingerprint = arg.fingerprint();
+
+            // Items deleted by retention would otherwise come back on the next fetch.
+            if purged.contains(&fingerprint) {
+                continue;
+            }
+
+            let decision = rule::apply(&matchers, &mut arg);
+            if decision.skip {
+                // Rules are applied again on every poll, which mustn't count the same
+                // item again.
+                if let Ok(matched) =
+                    rule::remember_skip(&self.conn, id, &fingerprint, &decision.matched)
+                {
+                    count_matches(report, &matched);
+                }
+                continue;
+            }
 
-        let args = items.iter().map(|x| ItemToCreate {
+            if item::create(&self.conn, &arg).is_ok() {
+                for name in &decision.tags {
+                    let _ = tag::create(&self.conn, id, &fingerprint, name);
+                }
+                // Fetched only once the item is known to be new, since old items are
+                // offered again on every poll of feeds that fetch them.
+                if feed.fetch_full_content {
+                    arg.content = self.fetch_article(&arg.link, articles).await;
+                    if let Some(content) = &arg.content {
+                        let _ = item::update_content(&self.conn, id, &fingerprint, content);
+                    }
+                }
+                count_matches(report, &decision.matched);
+                report.inserted.push(arg);
+            }
+        }
+    }
+
+    fn to_item(&self, feed: i32, x: &RawItem, current: DateTime<FixedOffset>) -> ItemToCreate {
+        let link = x.link.as_deref().unwrap_or("#").trim();
+        let source_link = x.source_link.as_deref().unwrap_or(link).trim();
+        ItemToCreate {
             author: x.author.clone().map(|x| x.trim().to_string()),
             title: x.title.trim().to_string(),
-            link: x.link.clone().unwrap_or("#".to_string()).trim().to_string(),
-            description: x.content.clone().unwrap_or_default().trim().to_string(),
+            // Resolving the link or stripping tracking parameters from it mustn't change
+            // which item this is.
+            link: self.clean_link(link),
+            source_link: Some(source_link.to_string()),
+            description: self.clean_description(x.content.as_deref().unwrap_or_default().trim()),
+            content: None,
             status: ItemStatus::Unread,
+            is_saved: false,
             published_at: x.published_at.unwrap_or(current),
             feed,
-        });
+        }
+    }
 
-        let mut inserted = vec![];
-        for arg in args {
-            if item::create(&self.conn, &arg).is_ok() {
-                inserted.push(arg);
-            }
+    /// Fetches and extracts the article at a link, at most once per run. Failures leave
+    /// the item with its description only.
+    async fn fetch_article(
+        &self,
+        link: &str,
+        articles: &mut HashMap<String, Option<String>>,
+    ) -> Option<String> {
+        if let Some(article) = articles.get(link) {
+            return article.clone();
         }
 
-        inserted
+        let article = match fetcher::get(link, self.proxy.as_deref()).await {
+            Ok(html) => extractor::extract(&html, link).map(|x| self.clean_description(&x)),
+            Err(_) => None,
+        };
+        articles.insert(link.to_string(), article.clone());
+
+        article
     }
 
-    fn get_most_recent_items(
-        &self,
-        feed_ids: &[i32],
-    ) -> Result<HashMap<i32, DateTime<FixedOffset>>> {
-        let mut most_recent_items = HashMap::new();
-
-        for feed_id in feed_ids {
-            let opt = ItemReadOption {
-                ids: None,
-                feed: Some(*feed_id),
-                status: None,
-                is_saved: None,
-                order_by: Some(ItemOrder::PublishedDateDesc),
-                limit: Some(1),
-                offset: None,
-            };
+    fn clean_link(&self, link: &str) -> String {
+        match &self.sanitize {
+            Some(policy) if policy.strip_tracking => sanitizer::strip_tracking_params(link),
+            _ => link.to_string(),
+        }
+    }
 
-            if let Some(item) = item::read_all(&self.conn, &opt)?.first() {
-                most_recent_items.insert(item.feed.id, item.published_at);
-            }
+    fn clean_description(&self, description: &str) -> String {
+        match &self.sanitize {
+            Some(policy) => sanitizer::sanitize(description, policy),
+            None => description.to_string(),
         }
+    }
+
+    fn get_most_recent_published_at(&self, feed: i32) -> Option<DateTime<FixedOffset>> {
+        item::latest_published_at(&self.conn, feed).unwrap_or_default()
+    }
+}
 
-        Ok(most_recent_items)
+fn count_matches(report: &mut Report, matched: &[i32]) {
+    for id in matched {
+        *report.matches.entry(*id).or_default() += 1;
     }
 }